@@ -24,9 +24,8 @@ async fn test_start() {
 /// Test deep link flow - when user clicks t.me/bot?start=987654321
 #[tokio::test]
 async fn test_with_deep_link() {
-    // Because https://t.me/some_bot?start=987654321 is the same as sending "/start 987654321",
-    // we can simulate it with this
-    let mock_message = MockMessageText::new().text("/start 987654321");
+    // Opening https://t.me/some_bot?start=987654321 is the same as sending "/start 987654321"
+    let mock_message = MockMessageText::deep_link("987654321");
     let mut bot = MockBot::new(mock_message, handler_tree()).await;
 
     bot.dependencies(deps![InMemStorage::<State>::new()]);
@@ -42,7 +41,7 @@ async fn test_with_deep_link() {
 #[tokio::test]
 async fn test_send_message_flow() {
     // Step 1: User clicks deep link to message user 987654321
-    let mock_message = MockMessageText::new().text("/start 987654321");
+    let mock_message = MockMessageText::deep_link("987654321");
     let mut bot = MockBot::new(mock_message.clone(), handler_tree()).await;
 
     let me = bot.me.clone();
@@ -65,9 +64,13 @@ async fn test_send_message_flow() {
     // And this is the confirmation message sent to the sender
     let response_message = responses.sent_messages[1].clone();
 
+    // The html markup is parsed, like Telegram does it
     assert_eq!(
         sent_message.text().unwrap(),
-        text::YOU_HAVE_A_NEW_MESSAGE.replace("{message}", "I love you!")
+        text::YOU_HAVE_A_NEW_MESSAGE
+            .replace("{message}", "I love you!")
+            .replace("<i>", "")
+            .replace("</i>", "")
     );
     assert_eq!(sent_message.chat.id.0, 987654321);
 
@@ -81,7 +84,7 @@ async fn test_send_message_flow() {
 /// Test wrong deep link format
 #[tokio::test]
 async fn test_wrong_link() {
-    let mock_message = MockMessageText::new().text("/start not_id");
+    let mock_message = MockMessageText::deep_link("not_id");
     let mut bot = MockBot::new(mock_message, handler_tree()).await;
     bot.dependencies(deps![InMemStorage::<State>::new()]);
 
@@ -95,7 +98,7 @@ async fn test_wrong_link() {
 #[tokio::test]
 async fn test_not_a_text() {
     // First arrive via deep link
-    let mock_message = MockMessageText::new().text("/start 987654321");
+    let mock_message = MockMessageText::deep_link("987654321");
     let mut bot = MockBot::new(mock_message, handler_tree()).await;
     bot.dependencies(deps![InMemStorage::<State>::new()]);
 
@@ -3,7 +3,7 @@ use core::sync::atomic::{AtomicI32, Ordering};
 use chrono::{DateTime, Utc};
 use teloxide::types::*;
 
-use super::chat::MockPrivateChat;
+use super::chat::{MockChannelChat, MockPrivateChat, MockSupergroupChat};
 use crate::{proc_macros::Changeable, MockUser};
 
 macro_rules! Message {
@@ -146,6 +146,148 @@ impl crate::dataset::IntoUpdate for MockEditedMessage {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockBusinessMessage(Message);
+
+impl MockBusinessMessage {
+    /// Creates a new MockBusinessMessage wrapper.
+    ///
+    /// This is useful for testing the `UpdateKind::BusinessMessage` variant.
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::BusinessConnectionId;
+    ///
+    /// let message = teremock::MockMessageText::new()
+    ///     .business_connection_id(BusinessConnectionId("connection_id".to_owned()))
+    ///     .build();
+    /// let business_message = teremock::MockBusinessMessage::new(message.clone());
+    /// assert_eq!(business_message.message(), &message);
+    /// ```
+    pub fn new(message: Message) -> Self {
+        Self(message)
+    }
+
+    /// Marks the message as sent by the business owner from an offline
+    /// (automatically scheduled) context, like an away or greeting message.
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::MessageKind;
+    ///
+    /// let message = teremock::MockMessageText::new().build();
+    /// let business_message = teremock::MockBusinessMessage::new(message).is_from_offline(true);
+    /// match &business_message.message().kind {
+    ///     MessageKind::Common(common) => assert!(common.is_from_offline),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn is_from_offline(mut self, is_from_offline: bool) -> Self {
+        if let MessageKind::Common(ref mut common) = self.0.kind {
+            common.is_from_offline = is_from_offline;
+        }
+        self
+    }
+
+    /// Sets the bot that sent the message on behalf of the business account.
+    ///
+    /// # Example
+    /// ```
+    /// let bot = teremock::MockUser::new().is_bot(true).first_name("Business bot").build();
+    /// let message = teremock::MockMessageText::new().build();
+    /// let business_message =
+    ///     teremock::MockBusinessMessage::new(message).sender_business_bot(bot.clone());
+    /// assert_eq!(business_message.message().sender_business_bot, Some(bot));
+    /// ```
+    pub fn sender_business_bot(mut self, bot: User) -> Self {
+        self.0.sender_business_bot = Some(bot);
+        self
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.0
+    }
+}
+
+impl crate::dataset::IntoUpdate for MockBusinessMessage {
+    /// Converts the business Message into an updates vector
+    ///
+    /// # Example
+    /// ```
+    /// use teremock::IntoUpdate;
+    /// use teloxide::types::{UpdateId, UpdateKind};
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let message = teremock::MockMessageText::new().build();
+    /// let business_message = teremock::MockBusinessMessage::new(message.clone());
+    /// let update = business_message.into_update(&AtomicI32::new(42))[0].clone();
+    ///
+    /// assert_eq!(update.id, UpdateId(42));
+    /// assert_eq!(update.kind, UpdateKind::BusinessMessage(message));
+    /// ```
+    ///
+    fn into_update(self, id: &AtomicI32) -> Vec<Update> {
+        vec![Update {
+            id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+            kind: UpdateKind::BusinessMessage(self.0),
+        }]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockChannelPost(Message);
+
+impl MockChannelPost {
+    /// Creates a new MockChannelPost wrapper.
+    ///
+    /// This is useful for testing the `UpdateKind::ChannelPost` variant.
+    /// The message is expected to already be in a channel chat.
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessageText::new()
+    ///     .chat(teremock::MockChannelChat::new().build())
+    ///     .author_signature("Author")
+    ///     .build();
+    /// let channel_post = teremock::MockChannelPost::new(message.clone());
+    /// assert_eq!(channel_post.message(), &message);
+    /// ```
+    pub fn new(message: Message) -> Self {
+        Self(message)
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.0
+    }
+}
+
+impl crate::dataset::IntoUpdate for MockChannelPost {
+    /// Converts the channel post into an updates vector
+    ///
+    /// # Example
+    /// ```
+    /// use teremock::IntoUpdate;
+    /// use teloxide::types::{UpdateId, UpdateKind};
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let message = teremock::MockMessageText::new()
+    ///     .chat(teremock::MockChannelChat::new().build())
+    ///     .build();
+    /// let channel_post = teremock::MockChannelPost::new(message.clone());
+    /// let update = channel_post.into_update(&AtomicI32::new(42))[0].clone();
+    ///
+    /// assert_eq!(update.id, UpdateId(42));
+    /// assert_eq!(update.kind, UpdateKind::ChannelPost(message));
+    /// ```
+    ///
+    fn into_update(self, id: &AtomicI32) -> Vec<Update> {
+        vec![Update {
+            id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+            kind: UpdateKind::ChannelPost(self.0),
+        }]
+    }
+}
+
 // More messages like Webapp data is needed
 
 Message! {
@@ -290,3 +432,264 @@ impl MockMessageNewChatMembers {
             }))
     }
 }
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageForumTopicCreated {
+        pub name: String,
+        pub icon_color: Rgb,
+        pub icon_custom_emoji_id: Option<CustomEmojiId>,
+    }
+}
+
+impl MockMessageForumTopicCreated {
+    pub const NAME: &'static str = "Topic name";
+    pub const ICON_COLOR: u32 = 0x6FB9F0;
+
+    /// Creates a new easily changeable forum topic created message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessageForumTopicCreated::new()
+    ///     .name("Announcements")
+    ///     .build();
+    /// assert_eq!(message.forum_topic_created().unwrap().name, "Announcements");
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(Self::NAME.to_owned(), Rgb::from_u32(Self::ICON_COLOR), None)
+    }
+
+    /// Builds the forum topic created message
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::Rgb;
+    ///
+    /// let mock_message = teremock::MockMessageForumTopicCreated::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message.forum_topic_created().unwrap().icon_color,
+    ///     Rgb::from_u32(teremock::MockMessageForumTopicCreated::ICON_COLOR)  // ICON_COLOR is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::ForumTopicCreated(MessageForumTopicCreated {
+                forum_topic_created: ForumTopicCreated {
+                    name: self.name,
+                    icon_color: self.icon_color,
+                    icon_custom_emoji_id: self.icon_custom_emoji_id,
+                },
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageGiveaway {
+        pub chats: Vec<Chat>,
+        pub winners_selection_date: DateTime<Utc>,
+        pub winner_count: u32,
+        pub only_new_members: bool,
+        pub has_public_winners: bool,
+        pub prize_description: Option<String>,
+        pub country_codes: Option<Vec<CountryCode>>,
+        pub prize_star_count: Option<u32>,
+        pub premium_subscription_month_count: Option<u8>,
+    }
+}
+
+impl MockMessageGiveaway {
+    pub const WINNER_COUNT: u32 = 1;
+
+    /// Creates a new easily changeable giveaway message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessageGiveaway::new()
+    ///     .winner_count(3u32)
+    ///     .build();
+    /// assert_eq!(message.giveaway().unwrap().winner_count, 3);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        let chat = MockChannelChat::new().build();
+        Self::new_message(
+            vec![chat.clone()],
+            Utc::now(),
+            Self::WINNER_COUNT,
+            false,
+            false,
+            None,
+            None,
+            None,
+            None,
+        )
+        .chat(chat)
+    }
+
+    /// Builds the giveaway message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teremock::MockMessageGiveaway::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message.giveaway().unwrap().winner_count,
+    ///     teremock::MockMessageGiveaway::WINNER_COUNT  // WINNER_COUNT is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::Giveaway(MessageGiveaway {
+                giveaway: Giveaway {
+                    chats: self.chats,
+                    winners_selection_date: self.winners_selection_date,
+                    winner_count: self.winner_count,
+                    only_new_members: self.only_new_members,
+                    has_public_winners: self.has_public_winners,
+                    prize_description: self.prize_description,
+                    country_codes: self.country_codes,
+                    prize_star_count: self.prize_star_count,
+                    premium_subscription_month_count: self.premium_subscription_month_count,
+                },
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageGiveawayWinners {
+        pub giveaway_chat: Chat,
+        pub giveaway_message_id: MessageId,
+        pub winners_selection_date: DateTime<Utc>,
+        pub winner_count: u32,
+        pub winners: Vec<User>,
+        pub additional_chat_count: Option<u16>,
+        pub prize_star_count: Option<u32>,
+        pub premium_subscription_month_count: Option<u8>,
+        pub unclaimed_prize_count: Option<u32>,
+        pub only_new_members: bool,
+        pub was_refunded: bool,
+        pub prize_description: Option<String>,
+    }
+}
+
+impl MockMessageGiveawayWinners {
+    pub const GIVEAWAY_MESSAGE_ID: i32 = 1;
+    pub const WINNER_COUNT: u32 = 1;
+
+    /// Creates a new easily changeable giveaway winners message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessageGiveawayWinners::new()
+    ///     .winner_count(2u32)
+    ///     .winners(vec![
+    ///         teremock::MockUser::new().id(1).build(),
+    ///         teremock::MockUser::new().id(2).build(),
+    ///     ])
+    ///     .build();
+    /// assert_eq!(message.giveaway_winners().unwrap().winner_count, 2);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        let chat = MockChannelChat::new().build();
+        Self::new_message(
+            chat.clone(),
+            MessageId(Self::GIVEAWAY_MESSAGE_ID),
+            Utc::now(),
+            Self::WINNER_COUNT,
+            vec![MockUser::new().build()],
+            None,
+            None,
+            None,
+            None,
+            false,
+            false,
+            None,
+        )
+        .chat(chat)
+    }
+
+    /// Builds the giveaway winners message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teremock::MockMessageGiveawayWinners::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message.giveaway_winners().unwrap().winner_count,
+    ///     teremock::MockMessageGiveawayWinners::WINNER_COUNT  // WINNER_COUNT is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::GiveawayWinners(MessageGiveawayWinners {
+                giveaway_winners: GiveawayWinners {
+                    chat: self.giveaway_chat,
+                    giveaway_message_id: self.giveaway_message_id,
+                    winners_selection_date: self.winners_selection_date,
+                    winner_count: self.winner_count,
+                    winners: self.winners,
+                    additional_chat_count: self.additional_chat_count,
+                    prize_star_count: self.prize_star_count,
+                    premium_subscription_month_count: self.premium_subscription_month_count,
+                    unclaimed_prize_count: self.unclaimed_prize_count,
+                    only_new_members: self.only_new_members,
+                    was_refunded: self.was_refunded,
+                    prize_description: self.prize_description,
+                },
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageBoostAdded {
+        pub boost_count: u16,
+    }
+}
+
+impl MockMessageBoostAdded {
+    pub const BOOST_COUNT: u16 = 1;
+
+    /// Creates a new easily changeable boost added message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessageBoostAdded::new()
+    ///     .boost_count(4u16)
+    ///     .build();
+    /// assert_eq!(message.boost_added().unwrap().boost_count, 4);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(Self::BOOST_COUNT).chat(MockSupergroupChat::new().build())
+    }
+
+    /// Builds the boost added message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teremock::MockMessageBoostAdded::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message.boost_added().unwrap().boost_count,
+    ///     teremock::MockMessageBoostAdded::BOOST_COUNT  // BOOST_COUNT is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::ChatBoostAdded(MessageChatBoostAdded {
+                boost_added: ChatBoostAdded {
+                    boost_count: self.boost_count,
+                },
+            }))
+    }
+}
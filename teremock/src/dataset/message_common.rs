@@ -124,6 +124,19 @@ impl MockMessageText {
         Self::new_message_common(Self::TEXT.to_string(), vec![], None)
     }
 
+    /// Creates a `/start <payload>` message text builder, which is what Telegram sends when a
+    /// user opens a `https://t.me/<bot>?start=<payload>` deep link
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessageText::deep_link("987654321").build();
+    /// assert_eq!(message.text().unwrap(), "/start 987654321");
+    /// ```
+    ///
+    pub fn deep_link(payload: &str) -> Self {
+        Self::new().text(format!("/start {payload}"))
+    }
+
     /// Builds the message text
     ///
     /// # Example
@@ -695,6 +708,56 @@ impl MockMessagePhoto {
     }
 }
 
+MessageCommon! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessagePaidMedia {
+        pub star_count: u32,
+        pub paid_media: Vec<PaidMedia>,
+    }
+}
+
+impl MockMessagePaidMedia {
+    pub const STAR_COUNT: u32 = 1;
+
+    /// Creates a new easily changable message paid media builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teremock::MockMessagePaidMedia::new()
+    ///     .star_count(50u32)
+    ///     .build();
+    /// assert_eq!(message.paid_media().unwrap().star_count, 50);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message_common(
+            Self::STAR_COUNT,
+            vec![PaidMedia::Photo(PaidMediaPhoto {
+                photo: MockPhotoSize::new().build(),
+            })],
+        )
+    }
+
+    /// Builds the message paid media
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teremock::MockMessagePaidMedia::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(message.paid_media().unwrap().paid_media.len(), 1);  // By default, there is only one photo, just MockPhotoSize::new().build()
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message_common(MediaKind::PaidMedia(MediaPaid {
+                paid_media: PaidMediaInfo {
+                    star_count: self.star_count,
+                    paid_media: self.paid_media,
+                },
+            }))
+    }
+}
+
 MessageCommon! {
     #[derive(Changeable, Clone)]
     pub struct MockMessagePoll {
@@ -128,6 +128,7 @@ pub struct MockMe {
     pub last_name: Option<String>,
     pub username: Option<String>,
     pub language_code: Option<String>,
+    pub is_premium: bool,
     pub can_join_groups: bool,
     pub can_read_all_group_messages: bool,
     pub supports_inline_queries: bool,
@@ -142,6 +143,7 @@ impl MockMe {
     pub const LAST_NAME: &'static str = "Bot";
     pub const USERNAME: &'static str = "test_bot";
     pub const LANGUAGE_CODE: &'static str = "en";
+    pub const IS_PREMIUM: bool = false;
     pub const CAN_JOIN_GROUPS: bool = false;
     pub const CAN_READ_ALL_GROUP_MESSAGES: bool = false;
     pub const SUPPORTS_INLINE_QUERIES: bool = false;
@@ -166,6 +168,7 @@ impl MockMe {
             last_name: Some(Self::LAST_NAME.to_string()),
             username: Some(Self::USERNAME.to_string()),
             language_code: Some(Self::LANGUAGE_CODE.to_string()),
+            is_premium: Self::IS_PREMIUM,
             can_join_groups: Self::CAN_JOIN_GROUPS,
             can_read_all_group_messages: Self::CAN_READ_ALL_GROUP_MESSAGES,
             supports_inline_queries: Self::SUPPORTS_INLINE_QUERIES,
@@ -192,6 +195,7 @@ impl MockMe {
         user.last_name = self.last_name;
         user.username = self.username;
         user.language_code = self.language_code;
+        user.is_premium = self.is_premium;
 
         Me {
             user: user.build(),
@@ -67,6 +67,8 @@
 //! - /EditMessageText
 //! - /EditMessageReplyMarkup
 //! - /EditMessageCaption
+//! - /EditMessageLiveLocation
+//! - /StopMessageLiveLocation
 //! - /GetFile
 //! - /SendMessage
 //! - /SendDocument
@@ -84,6 +86,7 @@
 //! - /SendSticker
 //! - /SendChatAction
 //! - /SendMediaGroup
+//! - /SendPaidMedia
 //! - /SendInvoice
 //! - /PinChatMessage
 //! - /UnpinChatMessage
@@ -93,9 +96,14 @@
 //! - /BanChatMember
 //! - /UnbanChatMember
 //! - /RestrictChatMember
+//! - /SetChatPermissions
+//! - /SetChatStickerSet
+//! - /DeleteChatStickerSet
 //! - /SetMessageReaction
 //! - /SetMyCommands
 //! - /GetMe
+//! - /GetChat
+//! - /GetChatMember
 //!
 //! ## Migration from teloxide_tests
 //!
@@ -130,9 +138,13 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::enum_variant_names)]
 
+// Lets the proc macros refer to `::teremock` from inside this crate too
+extern crate self as teremock;
+
 mod dataset;
 mod mock_bot;
 pub mod server;
+mod server_pool;
 pub(crate) mod state;
 #[cfg(test)]
 mod tests;
@@ -140,9 +152,39 @@ pub(crate) mod utils;
 
 pub use dataset::*;
 pub use mock_bot::{DistributionKey, MockBot};
-pub use server::Responses;
-use teloxide::types::{ChatId, MessageId, UserId};
+pub use server::{RawRequest, Responses, ResponsesView};
+pub use server_pool::ServerPool;
+use teloxide::types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup, MessageId, UserId};
 use teremock_macros as proc_macros;
+/// Turns an async function that takes a [`MockBot`] into a test.
+///
+/// The bot is made from the `update` and the handler `tree`, and passed in as the first argument.
+/// The test runs on a tokio runtime, just like with `#[tokio::test]`.
+///
+/// To use it with other test attributes that bring their own runtime and arguments, like
+/// `#[sqlx::test]`, add `no_runtime` and put `#[teremock::test]` above them. The bot argument is
+/// removed and the rest are left for the other attribute.
+///
+/// # Example
+/// ```no_run
+/// use teloxide::{dispatching::UpdateHandler, prelude::*};
+/// use teremock::{DistributionKey, HandlerError, MockBot, MockMessageText};
+///
+/// fn handler_tree() -> UpdateHandler<HandlerError> {
+///     Update::filter_message().endpoint(|bot: Bot, msg: Message| async move {
+///         bot.send_message(msg.chat.id, "Hello!").await?;
+///         Ok(())
+///     })
+/// }
+///
+/// #[teremock::test(update = MockMessageText::new().text("Hi!"), tree = handler_tree())]
+/// async fn greets(mut bot: MockBot<HandlerError, DistributionKey>) {
+///     bot.dispatch().await;
+///     teremock::assert_text!(bot, "Hello!");
+/// }
+/// ```
+///
+pub use teremock_macros::test;
 
 /// Error type alias commonly used with handler trees
 pub type HandlerError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -240,3 +282,54 @@ impl IntoMessageId for MessageId {
         self
     }
 }
+
+/// Builds an inline keyboard of callback buttons, one slice of `(label, callback_data)` pairs
+/// per row.
+///
+/// # Example
+/// ```
+/// let keyboard = teremock::inline_keyboard(&[&[("Yes", "yes"), ("No", "no")]]);
+/// assert_eq!(keyboard.inline_keyboard[0][1].text, "No");
+/// ```
+///
+pub fn inline_keyboard(rows: &[&[(&str, &str)]]) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(rows.iter().map(|row| {
+        row.iter()
+            .map(|&(label, data)| InlineKeyboardButton::callback(label, data))
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// Asserts that the last message sent by the bot has the expected text.
+///
+/// On a mismatch the panic message has the whole [`Responses::summary`], so it is easy to see
+/// what the bot actually did instead.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(mut bot: teremock::MockBot<teremock::HandlerError, teremock::DistributionKey>) {
+/// bot.dispatch().await;
+/// teremock::assert_text!(bot, "Hello!");
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! assert_text {
+    ($bot:expr, $expected:expr $(,)?) => {{
+        let responses = $bot.get_responses();
+        let expected = $expected;
+        let expected: &str = ::core::convert::AsRef::<str>::as_ref(&expected);
+        let actual = responses
+            .sent_messages
+            .last()
+            .and_then(|message| message.text());
+        if actual != Some(expected) {
+            panic!(
+                "assertion failed: expected the last sent text to be {:?}, got {:?}\n{}",
+                expected,
+                actual,
+                responses.summary()
+            );
+        }
+    }};
+}
@@ -8,24 +8,28 @@
 use std::{
     fmt::Debug,
     hash::Hash,
+    ops::ControlFlow,
     sync::{atomic::AtomicI32, Arc, Mutex},
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, FixedOffset};
 use teloxide::{
     dispatching::UpdateHandler,
     error_handlers::ErrorHandler,
     prelude::*,
     stop::mk_stop_token,
-    types::{MaybeInaccessibleMessage, Me, UpdateKind},
+    types::{Chat, ChatKind, MaybeInaccessibleMessage, Me, Message, Seconds, UpdateKind},
 };
 
 pub use crate::utils::DistributionKey;
 use crate::{
-    dataset::{IntoUpdate, MockMe},
+    dataset::{IntoUpdate, MockMe, MockPrivateChat, MockSupergroupChat},
     server,
     server::ServerManager,
     state::State,
     utils::default_distribution_function,
+    IntoChatId, IntoMessageId,
 };
 
 /// A mocked bot that sends requests to the fake server.
@@ -79,6 +83,7 @@ pub struct MockBot<Err, Key> {
     error_handler: Arc<dyn ErrorHandler<Err> + Send + Sync>,
 
     current_update_id: AtomicI32,
+    default_chat_kind: Option<ChatKind>,
     state: Arc<Mutex<State>>,
     /// Persistent server instance - started once, reused across all dispatches.
     /// When MockBot is dropped, the server's Drop impl triggers graceful shutdown.
@@ -128,35 +133,8 @@ where
         T: IntoUpdate,
         Err: Debug,
     {
-        let _ = pretty_env_logger::try_init();
-
-        let token = "1234567890:QWERTYUIOPASDFGHJKLZXCVBNMQWERTYUIO";
-        let bot = Bot::new(token);
-        let current_update_id = AtomicI32::new(42);
-        let state = Arc::new(Mutex::new(State::default()));
-        let me = MockMe::new().build();
-
-        // Start the server immediately - it will be reused for all dispatches
-        let server = ServerManager::start(me.clone(), state.clone())
+        Self::new_with_distribution_function(update, handler_tree, default_distribution_function)
             .await
-            .expect("Failed to start mock server");
-
-        let api_url = url::Url::parse(&format!("http://127.0.0.1:{}", server.port))
-            .expect("Failed to parse API URL");
-
-        Self {
-            bot,
-            me,
-            updates: update.into_update(&current_update_id),
-            handler_tree: Arc::new(handler_tree), // Wrap in Arc for efficient cloning
-            dependencies: DependencyMap::new(),
-            error_handler: LoggingErrorHandler::new(),
-            distribution_f: default_distribution_function,
-            current_update_id,
-            state,
-            server,
-            api_url,
-        }
     }
 }
 
@@ -167,6 +145,9 @@ where
 {
     /// Same as [`new`], but it inserts a distribution_function into the dispatcher
     ///
+    /// All of the updates are sent in one dispatch, updates with the same key are handled in
+    /// order, and updates with different keys are handled concurrently.
+    ///
     /// [`new`]: crate::MockBot::new
     pub async fn new_with_distribution_function<T>(
         update: T,
@@ -176,19 +157,35 @@ where
     where
         T: IntoUpdate,
         Err: Debug,
+    {
+        let state = Arc::new(Mutex::new(State::from_env()));
+
+        // Start the server immediately - it will be reused for all dispatches
+        let server = ServerManager::start(state.clone())
+            .await
+            .expect("Failed to start mock server");
+
+        Self::with_server(update, handler_tree, f, state, server)
+    }
+
+    /// Makes the bot around an already running server and its state
+    pub(crate) fn with_server<T>(
+        update: T,
+        handler_tree: UpdateHandler<Err>,
+        f: fn(&Update) -> Option<Key>,
+        state: Arc<Mutex<State>>,
+        server: ServerManager,
+    ) -> Self
+    where
+        T: IntoUpdate,
     {
         let _ = pretty_env_logger::try_init();
 
         let token = "1234567890:QWERTYUIOPASDFGHJKLZXCVBNMQWERTYUIO";
         let bot = Bot::new(token);
         let current_update_id = AtomicI32::new(42);
-        let state = Arc::new(Mutex::new(State::default()));
         let me = MockMe::new().build();
 
-        let server = ServerManager::start(me.clone(), state.clone())
-            .await
-            .expect("Failed to start mock server");
-
         let api_url = url::Url::parse(&format!("http://127.0.0.1:{}", server.port))
             .expect("Failed to parse API URL");
 
@@ -196,11 +193,12 @@ where
             bot,
             me,
             updates: update.into_update(&current_update_id),
-            handler_tree: Arc::new(handler_tree),
+            handler_tree: Arc::new(handler_tree), // Wrap in Arc for efficient cloning
             dependencies: DependencyMap::new(),
             error_handler: LoggingErrorHandler::new(),
             distribution_f: f,
             current_update_id,
+            default_chat_kind: None,
             state,
             server,
             api_url,
@@ -224,17 +222,71 @@ where
     }
 
     /// Sets the bot parameters (like supports_inline_queries, first_name, etc.)
+    ///
+    /// The fake server uses them right away, both for `get_me` and as the sender of messages.
     pub fn me(&mut self, me: MockMe) {
         self.me = me.build();
+        self.state.lock().unwrap().me = self.me.clone();
     }
 
     /// Sets the updates. Useful for reusing the same mocked bot instance.
     ///
-    /// You can pass in `vec![MockMessagePhoto]` or any other IntoUpdate type!
+    /// You can pass in `vec![MockMessagePhoto]` or any other IntoUpdate type! A plain `Update`
+    /// works too, so updates captured from the real Bot API can be replayed. Its id is replaced
+    /// with the next id of this bot.
     pub fn update<T: IntoUpdate>(&mut self, update: T) {
         self.updates = update.into_update(&self.current_update_id);
     }
 
+    /// Returns the updates that the next `dispatch()` will send, without dispatching them.
+    ///
+    /// Useful for checking what a complex mock (like a `Vec` of mocks) turned into.
+    pub fn pending_updates(&self) -> &[Update] {
+        &self.updates
+    }
+
+    /// Replaces the handler tree, the next `dispatch()` will use the new one.
+    ///
+    /// Useful to check that the bot behaves the same way with a different tree, like one built
+    /// with other feature flags. The server and the stored messages are kept.
+    pub fn set_handler(&mut self, handler_tree: UpdateHandler<Err>) {
+        self.handler_tree = Arc::new(handler_tree);
+    }
+
+    /// Sets the kind of chat the updates are sent in when their mocks don't specify one.
+    ///
+    /// Mocks default to a private chat with the user, so every update that is still in that
+    /// default chat is moved into a chat of the given kind. Public chats get the default
+    /// `MockSupergroupChat::ID` id. Updates with any other chat are left as they are.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.default_chat_kind(MockSupergroupChat::new().build().kind);
+    /// bot.dispatch().await;
+    /// ```
+    pub fn default_chat_kind(&mut self, kind: ChatKind) {
+        self.default_chat_kind = Some(kind);
+    }
+
+    /// Moves the message into the default chat kind, if the message is in the default chat
+    fn apply_default_chat(&self, message: &mut Message) {
+        let Some(kind) = &self.default_chat_kind else {
+            return;
+        };
+        if message.chat != MockPrivateChat::new().build() {
+            return;
+        }
+        let id = match kind {
+            ChatKind::Private(_) => message.chat.id,
+            ChatKind::Public(_) => ChatId(MockSupergroupChat::ID),
+        };
+        message.chat = Chat {
+            id,
+            kind: kind.clone(),
+        };
+    }
+
     /// Sets the error_handler for the Dispatcher
     pub fn error_handler(&mut self, handler: Arc<dyn ErrorHandler<Err> + Send + Sync>) {
         self.error_handler = handler;
@@ -250,19 +302,27 @@ where
         let mut state = self.state.lock().unwrap();
         for update in updates.iter_mut() {
             match &mut update.kind {
-                UpdateKind::Message(ref mut message) => {
+                UpdateKind::Message(ref mut message)
+                | UpdateKind::ChannelPost(ref mut message)
+                | UpdateKind::BusinessMessage(ref mut message) => {
+                    self.apply_default_chat(message);
                     state.add_message(message);
                 }
-                UpdateKind::EditedMessage(ref mut message) => {
+                UpdateKind::EditedMessage(ref mut message)
+                | UpdateKind::EditedChannelPost(ref mut message)
+                | UpdateKind::EditedBusinessMessage(ref mut message) => {
+                    self.apply_default_chat(message);
                     state.edit_message(message);
                 }
                 UpdateKind::CallbackQuery(ref mut callback) => {
                     if let Some(MaybeInaccessibleMessage::Regular(ref mut message)) =
                         callback.message
                     {
+                        self.apply_default_chat(message);
                         state.add_message(message);
                     }
                 }
+                UpdateKind::Poll(ref poll) => state.update_poll(poll),
                 _ => {}
             }
         }
@@ -312,10 +372,366 @@ where
         handle.await.expect("Dispatch task panicked!");
     }
 
+    /// Runs the first update through the handler tree and returns what the handler returned,
+    /// instead of passing errors to the error handler.
+    ///
+    /// Useful to unit test a single handler. Other updates are ignored, and if no handler
+    /// matched the update, `Ok(())` is returned. The responses are cleared before running,
+    /// the same way `dispatch()` does.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let result = bot.dispatch_once().await;
+    /// assert!(result.is_err());
+    /// ```
+    pub async fn dispatch_once(&mut self) -> Result<(), Err> {
+        self.state.lock().unwrap().reset();
+
+        let mut updates: Vec<Update> = self.updates.iter().take(1).cloned().collect();
+        self.insert_updates(&mut updates);
+        let Some(update) = updates.pop() else {
+            return Ok(());
+        };
+
+        let bot = self.bot.clone().set_api_url(self.api_url.clone());
+        let handler_tree = Arc::clone(&self.handler_tree);
+        let mut deps = self.dependencies.clone();
+        deps.insert(self.me.clone());
+        deps.insert(bot);
+        deps.insert(update);
+
+        // Same as in `dispatch()`, the handler runs in its own task
+        let handle = tokio::task::spawn(async move {
+            match handler_tree.dispatch(deps).await {
+                ControlFlow::Break(result) => result,
+                ControlFlow::Continue(_) => Ok(()),
+            }
+        });
+
+        handle.await.expect("Dispatch task panicked!")
+    }
+
+    /// Waits until the fake server has had no requests in flight for a short while.
+    ///
+    /// `dispatch` returns once the handlers are done, but if a handler spawned a task that sends
+    /// requests on its own, those may still be on their way. This is only a best effort: the
+    /// server can't see a request the bot hasn't sent yet, so a task that waits for something
+    /// before sending may still be running when this returns.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.dispatch().await;
+    /// bot.await_quiescence().await;
+    /// let responses = bot.get_responses();
+    /// ```
+    pub async fn await_quiescence(&self) {
+        const IDLE_PERIOD: Duration = Duration::from_millis(100);
+        const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+        let mut idle_since = Instant::now();
+        loop {
+            if self.in_flight() > 0 {
+                idle_since = Instant::now();
+            } else if idle_since.elapsed() >= IDLE_PERIOD {
+                return;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Returns how many requests the fake server has received, but not yet responded to
+    pub(crate) fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Makes the polls sent by the bot get sequential ids, starting from `first_id`.
+    ///
+    /// By default every sent poll has the id `MockMessagePoll::POLL_ID`. With seeded ids, the
+    /// first poll sent after this call gets `first_id`, the next one `first_id + 1`, and so on,
+    /// across dispatches.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.seed_poll_ids(100);
+    /// bot.dispatch().await;
+    /// let poll = bot.get_responses().sent_messages_poll[0].message.poll().unwrap().clone();
+    /// assert_eq!(poll.id.0, "100");
+    /// ```
+    pub fn seed_poll_ids(&self, first_id: u64) {
+        self.state.lock().unwrap().next_poll_id = Some(first_id);
+    }
+
+    /// Makes the fake server store the body of every request exactly as teloxide sent it.
+    ///
+    /// The bodies end up in `Responses::raw_requests`. Useful to check the serialization of
+    /// things like keyboards, which are otherwise only seen after being parsed by the server.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.capture_raw_requests(true);
+    /// bot.dispatch().await;
+    /// let reply_markup = bot.get_responses().last_raw_reply_markup().unwrap();
+    /// assert!(reply_markup.contains(r#""callback_data":"yes""#));
+    /// ```
+    pub fn capture_raw_requests(&self, enabled: bool) {
+        self.state.lock().unwrap().capture_raw_requests = enabled;
+    }
+
+    /// Links a channel to its discussion group.
+    ///
+    /// Every post the bot sends to the channel is then automatically forwarded to the group, the
+    /// same way Telegram does it. The forwarded copies are in `Responses::automatic_forwards`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.link_chats(ChatId(-100), ChatId(-200));
+    /// bot.dispatch().await;
+    /// let forward = &bot.get_responses().automatic_forwards[0];
+    /// assert_eq!(forward.chat.id, ChatId(-200));
+    /// ```
+    pub fn link_chats(&self, channel_id: impl IntoChatId, group_id: impl IntoChatId) {
+        self.state
+            .lock()
+            .unwrap()
+            .linked_chats
+            .insert(channel_id.into_chat_id().0, group_id.into_chat_id().0);
+    }
+
+    /// Registers a chat, so the messages the bot sends to it carry the chat as it is here, with
+    /// its title and username, instead of a bare id, and `get_chat` describes it as such. The
+    /// chat is found by its id, or by its username if the bot sends to `@username`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.register_chat(MockSupergroupChat::new().id(-100).title("Book club").build());
+    /// bot.dispatch().await; // Messages sent to ChatId(-100) have "Book club" as the title
+    /// ```
+    pub fn register_chat(&self, chat: Chat) {
+        self.state.lock().unwrap().chats.insert(chat.id.0, chat);
+    }
+
+    /// Enables slow mode in a supergroup, so `get_chat` reports the delay as `slow_mode_delay`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.slow_mode(ChatId(-100), Seconds::from_seconds(30));
+    /// bot.dispatch().await; // `bot.get_chat(ChatId(-100))` has a 30 seconds slow_mode_delay
+    /// ```
+    pub fn slow_mode(&self, chat_id: impl IntoChatId, delay: Seconds) {
+        self.state
+            .lock()
+            .unwrap()
+            .slow_mode_delays
+            .insert(chat_id.into_chat_id().0, delay);
+    }
+
+    /// Stops the clock of the fake server at `now`. The messages the bot sends are dated with it,
+    /// and so are the edits.
+    ///
+    /// Telegram sends dates as Unix timestamps, so teloxide always has them in UTC and the offset
+    /// itself can't survive. The instant is the same though, so the local time can be restored
+    /// with `message.date.with_timezone(now.offset())`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let now = DateTime::parse_from_rfc3339("2024-05-01T09:30:00+03:00").unwrap();
+    /// bot.set_clock_tz(now);
+    /// bot.dispatch().await;
+    /// assert_eq!(bot.get_responses().sent_messages[0].date, now);
+    /// ```
+    pub fn set_clock_tz(&self, now: DateTime<FixedOffset>) {
+        self.state.lock().unwrap().clock = Some(now);
+    }
+
+    /// Makes the fake server wait before handling every request, to imitate a slow network.
+    ///
+    /// Defaults to the `TEREMOCK_LATENCY_MS` environment variable, or no latency at all.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.latency(Duration::from_millis(50));
+    /// bot.dispatch().await; // Every request takes at least 50ms now
+    /// ```
+    pub fn latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// Makes `get_responses` panic if the bot called endpoints that the fake server doesn't
+    /// implement, instead of just letting the bot get an error it might swallow.
+    ///
+    /// Defaults to the `TEREMOCK_STRICT` environment variable being `1` or `true`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.strict(true);
+    /// bot.dispatch().await;
+    /// bot.get_responses(); // Panics if the bot called an unimplemented endpoint
+    /// ```
+    pub fn strict(&self, enabled: bool) {
+        self.state.lock().unwrap().strict = enabled;
+    }
+
+    /// Turns on strict mode, the same as `strict(true)`. The unimplemented endpoints the bot
+    /// calls are recorded, and the next `get_responses` or `verify` panics listing them.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.fail_on_unimplemented();
+    /// bot.dispatch().await;
+    /// bot.verify(); // Panics if the bot called an unimplemented endpoint
+    /// ```
+    pub fn fail_on_unimplemented(&self) {
+        self.strict(true);
+    }
+
+    /// Panics in strict mode, if the bot called endpoints that the fake server doesn't
+    /// implement, naming them. Does nothing otherwise.
+    #[track_caller]
+    pub fn verify(&self) {
+        // Panicking with the lock held would poison the state for everything after, like drop
+        let (strict, unimplemented_calls) = {
+            let state = self.state.lock().unwrap();
+            (state.strict, state.unimplemented_calls.clone())
+        };
+        if strict && !unimplemented_calls.is_empty() {
+            panic!(
+                "The bot called unimplemented endpoints: {}",
+                unimplemented_calls.join(", ")
+            );
+        }
+    }
+
+    /// Returns the `language_code` of the user who sent the latest of the updates, the one
+    /// handlers see as `from`. `None` if there is no sender, or the sender has no language set.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let user = MockUser::new().language_code("fr").build();
+    /// let bot = MockBot::new(MockMessageText::new().from(user), handler_tree()).await;
+    /// assert_eq!(bot.last_user_language(), Some("fr"));
+    /// ```
+    pub fn last_user_language(&self) -> Option<&str> {
+        self.updates
+            .iter()
+            .rev()
+            .find_map(|update| update.from())?
+            .language_code
+            .as_deref()
+    }
+
+    /// Deletes a message from the fake server without the bot knowing, like when a user or an
+    /// admin deletes it. Editing or replying to it fails afterwards, just like in Telegram.
+    ///
+    /// Returns the deleted message, or `None` if there was no message with this id.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.dispatch().await;
+    /// let sent = bot.get_responses().sent_messages[0].id;
+    /// bot.delete_stored_message(sent);
+    /// bot.dispatch().await; // Editing `sent` now fails with `MessageToEditNotFound`
+    /// ```
+    pub fn delete_stored_message(&self, message_id: impl IntoMessageId) -> Option<Message> {
+        self.state
+            .lock()
+            .unwrap()
+            .messages
+            .delete_message(message_id.into_message_id().0)
+    }
+
+    /// Returns how many messages are currently stored by the fake server.
+    ///
+    /// This includes the messages from the updates and everything the bot has sent, minus the
+    /// deleted messages. Unlike `get_responses`, the store is not cleared between dispatches.
+    pub fn stored_message_count(&self) -> usize {
+        self.state.lock().unwrap().messages.messages.len()
+    }
+
+    /// Takes the request log of the last dispatch, leaving it empty.
+    ///
+    /// There is one line per request the bot made, with the method name, the status code and,
+    /// if the request failed, the error the server responded with. Useful to find out why a route
+    /// returned a 400. The `log::` output of the server isn't captured here, it still goes to the
+    /// logger of the test, if there is one.
+    pub fn take_request_log(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.lock().unwrap().request_log)
+    }
+
     /// Returns the responses stored in `responses`
+    ///
+    /// # Panics
+    ///
+    /// In strict mode, if the bot called endpoints that the fake server doesn't implement.
+    #[track_caller]
     pub fn get_responses(&self) -> server::Responses {
+        self.verify();
         self.state.lock().unwrap().responses.clone()
     }
+
+    /// Asserts that a message with exactly this text was sent to the chat during the last
+    /// dispatch, panicking with the summary of all responses if it wasn't.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// bot.dispatch().await;
+    /// bot.assert_sent_to(ChatId(-1001234), "Welcome to the group!");
+    /// ```
+    #[track_caller]
+    pub fn assert_sent_to(&self, chat_id: impl IntoChatId, text: &str) {
+        let chat_id = chat_id.into_chat_id();
+        let responses = self.get_responses();
+        if !responses
+            .sent_messages
+            .iter()
+            .any(|message| message.chat.id == chat_id && message.text() == Some(text))
+        {
+            panic!(
+                "assertion failed: expected {:?} to be sent to chat {}\n{}",
+                text,
+                chat_id.0,
+                responses.summary()
+            );
+        }
+    }
+
+    /// Clears the responses and the request log, the same way `dispatch()` does before running.
+    ///
+    /// The stored messages are kept, so the bot can still edit or reply to them later.
+    pub fn clear_responses(&self) {
+        self.state.lock().unwrap().reset();
+    }
+}
+
+impl<Err, Key> Debug for MockBot<Err, Key> {
+    /// Shows the port of the fake server, the amount of pending updates, the dependencies by
+    /// their type names, and the summary of the responses of the last dispatch. Handy for
+    /// `dbg!(&bot)` while writing a test.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let responses = match self.state.lock() {
+            Ok(state) => state.responses.summary(),
+            Err(_) => "<poisoned>".to_string(),
+        };
+        f.debug_struct("MockBot")
+            .field("port", &self.server.port)
+            .field("pending_updates", &self.updates.len())
+            .field("dependencies", &self.dependencies)
+            .field("responses", &responses)
+            .finish_non_exhaustive()
+    }
 }
 
 /// A simple update listener that processes updates and stops.
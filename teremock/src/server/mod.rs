@@ -8,35 +8,43 @@ use std::{
 };
 
 use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::{from_fn, Next},
     web::{self, get, post, scope, Data, ServiceConfig},
-    App, HttpResponse, HttpServer, Responder,
+    App, HttpMessage as _, HttpResponse, HttpServer, Responder,
 };
+use futures_util::StreamExt as _;
 pub use responses::*;
 use routes::{
-    answer_callback_query::*, ban_chat_member::*, copy_message::*, delete_message::*,
-    delete_messages::*, download_file::download_file, edit_message_caption::*,
-    edit_message_reply_markup::*, edit_message_text::*, forward_message::*, get_file::*, get_me::*,
-    get_updates::*, get_webhook_info::*, pin_chat_message::*, restrict_chat_member::*,
-    send_animation::*, send_audio::*, send_chat_action::*, send_contact::*, send_dice::*,
-    send_document::*, send_invoice::*, send_location::*, send_media_group::*, send_message::*,
+    answer_callback_query::*, ban_chat_member::*, copy_message::*, delete_chat_sticker_set::*,
+    delete_message::*, delete_messages::*, download_file::download_file, edit_message_caption::*,
+    edit_message_live_location::*, edit_message_reply_markup::*, edit_message_text::*,
+    forward_message::*, get_chat::*, get_chat_member::*, get_file::*, get_me::*, get_updates::*,
+    get_webhook_info::*, pin_chat_message::*, restrict_chat_member::*, send_animation::*,
+    send_audio::*, send_chat_action::*, send_contact::*, send_dice::*, send_document::*,
+    send_invoice::*, send_location::*, send_media_group::*, send_message::*, send_paid_media::*,
     send_photo::*, send_poll::*, send_sticker::*, send_venue::*, send_video::*, send_video_note::*,
-    send_voice::*, set_message_reaction::*, set_my_commands::*, unban_chat_member::*,
+    send_voice::*, set_chat_permissions::*, set_chat_sticker_set::*, set_message_reaction::*,
+    set_my_commands::*, stop_message_live_location::*, unban_chat_member::*,
     unpin_all_chat_messages::*, unpin_chat_message::*,
 };
 pub use routes::{
     copy_message::CopyMessageBody, delete_message::DeleteMessageBody,
     delete_messages::DeleteMessagesBody, edit_message_caption::EditMessageCaptionBody,
+    edit_message_live_location::EditMessageLiveLocationBody,
     edit_message_reply_markup::EditMessageReplyMarkupBody, edit_message_text::EditMessageTextBody,
     forward_message::ForwardMessageBody, send_animation::SendMessageAnimationBody,
     send_audio::SendMessageAudioBody, send_contact::SendMessageContactBody,
     send_dice::SendMessageDiceBody, send_document::SendMessageDocumentBody,
     send_invoice::SendMessageInvoiceBody, send_location::SendMessageLocationBody,
     send_media_group::SendMediaGroupBody, send_message::SendMessageTextBody,
-    send_photo::SendMessagePhotoBody, send_poll::SendMessagePollBody,
-    send_sticker::SendMessageStickerBody, send_venue::SendMessageVenueBody,
-    send_video::SendMessageVideoBody, send_video_note::SendMessageVideoNoteBody,
+    send_paid_media::SendPaidMediaBody, send_photo::SendMessagePhotoBody,
+    send_poll::SendMessagePollBody, send_sticker::SendMessageStickerBody,
+    send_venue::SendMessageVenueBody, send_video::SendMessageVideoBody,
+    send_video_note::SendMessageVideoNoteBody,
+    stop_message_live_location::StopMessageLiveLocationBody,
 };
-use teloxide::types::Me;
 use tokio::{
     sync::mpsc::{channel, Sender},
     task::{JoinError, JoinHandle},
@@ -68,7 +76,7 @@ impl Drop for ServerManager {
 
 #[warn(clippy::unwrap_used)]
 impl ServerManager {
-    pub(crate) async fn start(me: Me, state: Arc<Mutex<State>>) -> Result<Self, Box<dyn Error>> {
+    pub(crate) async fn start(state: Arc<Mutex<State>>) -> Result<Self, Box<dyn Error>> {
         let listener = TcpListener::bind("127.0.0.1:0")?;
         let port = listener.local_addr()?.port();
 
@@ -77,7 +85,6 @@ impl ServerManager {
 
         let server = tokio::spawn(run_server(
             listener,
-            me,
             state.clone(),
             cancel_token.clone(),
             tx,
@@ -111,12 +118,11 @@ impl ServerManager {
 
 async fn run_server(
     listener: TcpListener,
-    me: Me,
     state: Arc<Mutex<State>>,
     cancel_token: CancellationToken,
     tx: Sender<()>,
 ) {
-    let server = match create_server(listener, me, state) {
+    let server = match create_server(listener, state) {
         Ok(server) => server,
         Err(e) => {
             log::error!("Failed to create mock server: {}", e);
@@ -143,13 +149,15 @@ async fn run_server(
 
 fn create_server(
     listener: TcpListener,
-    me: Me,
     state: Arc<Mutex<State>>,
 ) -> io::Result<actix_web::dev::Server> {
     Ok(HttpServer::new(move || {
         App::new()
-            .app_data(Data::new(me.clone()))
             .app_data(Data::from(state.clone()))
+            .wrap(from_fn(simulate_latency))
+            .wrap(from_fn(capture_raw_requests))
+            .wrap(from_fn(log_requests))
+            .wrap(from_fn(track_in_flight))
             .configure(set_routes)
     })
     // Use single worker for test server - no need for multiple workers
@@ -158,6 +166,104 @@ fn create_server(
     .run())
 }
 
+/// Decrements the in-flight counter when the request is finished, even if the handler errors
+struct InFlightGuard(Data<Mutex<State>>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut lock) = self.0.lock() {
+            lock.in_flight -= 1;
+        }
+    }
+}
+
+async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let _guard = req.app_data::<Data<Mutex<State>>>().and_then(|state| {
+        state.lock().ok()?.in_flight += 1;
+        Some(InFlightGuard(state.clone()))
+    });
+    next.call(req).await
+}
+
+/// Records a log line for every handled request, with the error if the route failed, and the
+/// name of the called method
+async fn log_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let state = req.app_data::<Data<Mutex<State>>>().cloned();
+    let res = next.call(req).await?;
+
+    let path = res.request().path();
+    let method = path.rsplit('/').next().unwrap_or_default();
+    let line = match res.response().error() {
+        Some(error) => format!("{} {}: {}", method, res.status(), error),
+        None => format!("{} {}", method, res.status()),
+    };
+    log::debug!("{}", line);
+    if let Some(mut lock) = state.as_ref().and_then(|state| state.lock().ok()) {
+        lock.request_log.push(line);
+        // File downloads are at `/file/bot<token>/<path>`, they aren't calls of methods
+        if path.starts_with("/bot") {
+            lock.responses.endpoint_calls.push(method.to_owned());
+        }
+    }
+
+    Ok(res)
+}
+
+/// Waits for the latency set with `MockBot::latency` before handling the request
+async fn simulate_latency(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let latency = req
+        .app_data::<Data<Mutex<State>>>()
+        .and_then(|state| state.lock().ok())
+        .map(|lock| lock.latency)
+        .unwrap_or_default();
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    next.call(req).await
+}
+
+/// Stores the body of every request exactly as it was sent, if raw requests are captured
+async fn capture_raw_requests(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let state = req.app_data::<Data<Mutex<State>>>().cloned();
+    let enabled = state
+        .as_ref()
+        .and_then(|state| state.lock().ok())
+        .is_some_and(|lock| lock.capture_raw_requests);
+    if !enabled {
+        return next.call(req).await;
+    }
+
+    let mut body = web::BytesMut::new();
+    let mut payload = req.take_payload();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk?);
+    }
+    let body = body.freeze();
+
+    let method = req.path().rsplit('/').next().unwrap_or_default().to_owned();
+    if let Some(mut lock) = state.as_ref().and_then(|state| state.lock().ok()) {
+        lock.responses.raw_requests.push(RawRequest {
+            method,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        });
+    }
+    req.set_payload(body.into());
+
+    next.call(req).await
+}
+
 fn set_routes(cfg: &mut ServiceConfig) {
     cfg.route("/file/bot{token}/{file_name}", get().to(download_file))
         .service(scope("/bot{token}").configure(set_bot_routes));
@@ -184,6 +290,7 @@ fn set_bot_routes(cfg: &mut ServiceConfig) {
         .route("/SendDice", post().to(send_dice))
         .route("/SendPoll", post().to(send_poll))
         .route("/SendMediaGroup", post().to(send_media_group))
+        .route("/SendPaidMedia", post().to(send_paid_media))
         .route("/SendInvoice", post().to(send_invoice))
         .route("/EditMessageText", post().to(edit_message_text))
         .route("/EditMessageCaption", post().to(edit_message_caption))
@@ -191,6 +298,14 @@ fn set_bot_routes(cfg: &mut ServiceConfig) {
             "/EditMessageReplyMarkup",
             post().to(edit_message_reply_markup),
         )
+        .route(
+            "/EditMessageLiveLocation",
+            post().to(edit_message_live_location),
+        )
+        .route(
+            "/StopMessageLiveLocation",
+            post().to(stop_message_live_location),
+        )
         .route("/DeleteMessage", post().to(delete_message))
         .route("/DeleteMessages", post().to(delete_messages))
         .route("/ForwardMessage", post().to(forward_message))
@@ -202,11 +317,24 @@ fn set_bot_routes(cfg: &mut ServiceConfig) {
         .route("/BanChatMember", post().to(ban_chat_member))
         .route("/UnbanChatMember", post().to(unban_chat_member))
         .route("/RestrictChatMember", post().to(restrict_chat_member))
+        .route("/SetChatPermissions", post().to(set_chat_permissions))
+        .route("/SetChatStickerSet", post().to(set_chat_sticker_set))
+        .route("/DeleteChatStickerSet", post().to(delete_chat_sticker_set))
+        .route("/GetChat", post().to(get_chat))
+        .route("/GetChatMember", post().to(get_chat_member))
         .route("/SetMessageReaction", post().to(set_message_reaction))
         .route("/SetMyCommands", post().to(set_my_commands))
         .route("/{unknown_endpoint}", post().to(unknown_endpoint));
 }
 
-async fn unknown_endpoint(path: web::Path<(String, String)>) -> impl Responder {
+async fn unknown_endpoint(
+    path: web::Path<(String, String)>,
+    state: web::Data<Mutex<State>>,
+) -> impl Responder {
+    if let Ok(mut lock) = state.lock() {
+        if lock.strict {
+            lock.unimplemented_calls.push(path.1.clone());
+        }
+    }
     HttpResponse::InternalServerError().message_body(format!("Endpoint \"{}\" is not yet implemented! Please make an issue to https://github.com/LasterAlex/teloxide_tests/issues/new?assignees=&labels=no+endpoint&projects=&template=add-endpoint-template.md&title=", path.1))
 }
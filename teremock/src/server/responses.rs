@@ -1,15 +1,200 @@
-use teloxide::types::{Message, MessageId};
+use std::{collections::HashMap, fmt::Write};
+
+use teloxide::types::{
+    BusinessConnectionId, ChatAction, EffectId, InlineKeyboardButton, InlineKeyboardButtonKind,
+    Message, MessageEntity, MessageId, ReplyMarkup, ReplyParameters, ThreadId, User,
+};
 
 use super::routes::{
-    answer_callback_query::*, ban_chat_member::*, copy_message::*, delete_message::*,
-    edit_message_caption::*, edit_message_reply_markup::*, edit_message_text::*,
+    answer_callback_query::*, ban_chat_member::*, copy_message::*, delete_chat_sticker_set::*,
+    delete_message::*, edit_message_caption::*, edit_message_live_location::*,
+    edit_message_reply_markup::*, edit_message_text::*, entities::quote_with_entities,
     forward_message::*, pin_chat_message::*, restrict_chat_member::*, send_animation::*,
     send_audio::*, send_chat_action::*, send_contact::*, send_dice::*, send_document::*,
-    send_invoice::*, send_location::*, send_media_group::*, send_message::*, send_photo::*,
-    send_poll::*, send_sticker::*, send_venue::*, send_video::*, send_video_note::*, send_voice::*,
-    set_message_reaction::*, set_my_commands::*, unban_chat_member::*, unpin_all_chat_messages::*,
-    unpin_chat_message::*,
+    send_invoice::*, send_location::*, send_media_group::*, send_message::*, send_paid_media::*,
+    send_photo::*, send_poll::*, send_sticker::*, send_venue::*, send_video::*, send_video_note::*,
+    send_voice::*, set_chat_permissions::*, set_chat_sticker_set::*, set_message_reaction::*,
+    set_my_commands::*, stop_message_live_location::*, unban_chat_member::*,
+    unpin_all_chat_messages::*, unpin_chat_message::*,
 };
+use crate::IntoChatId;
+
+/// A request exactly as teloxide sent it to the fake server.
+///
+/// Only captured after `MockBot::capture_raw_requests(true)`.
+#[derive(Clone, Debug)]
+pub struct RawRequest {
+    /// The name of the called method, like `SendMessage`
+    pub method: String,
+    /// The body of the request, either JSON or multipart form data
+    pub body: String,
+}
+
+impl RawRequest {
+    /// Returns the value of a top level field exactly as it was serialized, or `None` if the
+    /// request doesn't have it.
+    ///
+    /// # Example
+    /// ```
+    /// let request = teremock::RawRequest {
+    ///     method: "SendMessage".to_string(),
+    ///     body: r#"{"chat_id":1,"text":"Hi","reply_markup":{"force_reply":true}}"#.to_string(),
+    /// };
+    /// assert_eq!(request.field("reply_markup").unwrap(), r#"{"force_reply":true}"#);
+    /// assert_eq!(request.field("parse_mode"), None);
+    /// ```
+    pub fn field(&self, name: &str) -> Option<String> {
+        if let Ok(fields) =
+            serde_json::from_str::<HashMap<String, &serde_json::value::RawValue>>(&self.body)
+        {
+            return fields.get(name).map(|value| value.get().to_owned());
+        }
+        // Multipart form data, the value goes after the headers of its part
+        let header = format!("name=\"{name}\"");
+        let part = &self.body[self.body.find(&header)? + header.len()..];
+        let value = &part[part.find("\r\n\r\n")? + 4..];
+        Some(value[..value.find("\r\n--")?].to_owned())
+    }
+}
+
+/// Defines [`Responses`], and the methods that go over all of its fields of some kind, so a new
+/// field can't be forgotten in them. Every field has its kind in `#[record(...)]`: `sent` for the
+/// sent messages, `edit` for the edits, `call` for the rest of the recorded requests, and `other`
+/// for everything else.
+macro_rules! responses {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[doc = $doc:expr])*
+                #[record($kind:ident)]
+                pub $field:ident: $ty:ty,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name {
+            $(
+                $(#[doc = $doc])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $name {
+            /// Returns the records of all sent messages, of every kind
+            fn sent_records(&self) -> impl Iterator<Item = &dyn SentRecord> {
+                std::iter::empty()$(.chain(responses!(@sent $kind self.$field)))*
+            }
+
+            /// Returns how many requests were recorded, in all fields but the `other` ones
+            fn recorded_calls(&self) -> usize {
+                0 $(+ responses!(@calls $kind self.$field))*
+            }
+
+            /// Returns how many edits were recorded
+            fn recorded_edits(&self) -> usize {
+                0 $(+ responses!(@edits $kind self.$field))*
+            }
+
+            /// Returns the names of the `edit` and `call` fields, with how many requests are in
+            /// each of them
+            fn recorded_counts(&self) -> Vec<(&'static str, usize)> {
+                [$(responses!(@count $kind $field self.$field)),*]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+        }
+    };
+    (@sent sent $field:expr) => {
+        $field.iter().map(|sent| sent as &dyn SentRecord)
+    };
+    (@sent $kind:ident $field:expr) => {
+        std::iter::empty()
+    };
+    (@calls other $field:expr) => {
+        0
+    };
+    (@calls $kind:ident $field:expr) => {
+        $field.len()
+    };
+    (@edits edit $field:expr) => {
+        $field.len()
+    };
+    (@edits $kind:ident $field:expr) => {
+        0
+    };
+    (@count edit $name:ident $field:expr) => {
+        Some((stringify!($name), $field.len()))
+    };
+    (@count call $name:ident $field:expr) => {
+        Some((stringify!($name), $field.len()))
+    };
+    (@count $kind:ident $name:ident $field:expr) => {
+        None
+    };
+}
+
+/// A record of sent messages, with the request they were sent with
+trait SentRecord {
+    fn messages(&self) -> &[Message];
+    fn reply_markup(&self) -> Option<&ReplyMarkup>;
+    fn reply_parameters(&self) -> Option<&ReplyParameters>;
+}
+
+macro_rules! impl_sent_record {
+    ($($sent:ty),* $(,)?) => {
+        $(
+            impl SentRecord for $sent {
+                fn messages(&self) -> &[Message] {
+                    std::slice::from_ref(&self.message)
+                }
+
+                fn reply_markup(&self) -> Option<&ReplyMarkup> {
+                    self.bot_request.reply_markup.as_ref()
+                }
+
+                fn reply_parameters(&self) -> Option<&ReplyParameters> {
+                    self.bot_request.reply_parameters.as_ref()
+                }
+            }
+        )*
+    };
+}
+
+impl_sent_record!(
+    SentMessageText,
+    SentMessagePaidMedia,
+    SentMessagePhoto,
+    SentMessageVideo,
+    SentMessageAudio,
+    SentMessageVoice,
+    SentMessageVideoNote,
+    SentMessageDocument,
+    SentMessageAnimation,
+    SentMessageLocation,
+    SentMessageVenue,
+    SentMessageContact,
+    SentMessageDice,
+    SentMessagePoll,
+    SentMessageSticker,
+    SentMessageInvoice,
+);
+
+impl SentRecord for SentMediaGroup {
+    fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    fn reply_markup(&self) -> Option<&ReplyMarkup> {
+        // Telegram doesn't allow reply markup on media groups
+        None
+    }
+
+    fn reply_parameters(&self) -> Option<&ReplyParameters> {
+        self.bot_request.reply_parameters.as_ref()
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct SentMessageText {
@@ -18,6 +203,12 @@ pub struct SentMessageText {
     pub bot_request: SendMessageTextBody,
 }
 
+#[derive(Clone, Debug)]
+pub struct SentMessagePaidMedia {
+    pub message: Message,
+    pub bot_request: SendPaidMediaBody,
+}
+
 #[derive(Clone, Debug)]
 pub struct SentMessagePhoto {
     pub message: Message,
@@ -120,6 +311,18 @@ pub struct EditedMessageCaption {
     pub bot_request: EditMessageCaptionBody,
 }
 
+#[derive(Clone, Debug)]
+pub struct EditedMessageLiveLocation {
+    pub message: Message,
+    pub bot_request: EditMessageLiveLocationBody,
+}
+
+#[derive(Clone, Debug)]
+pub struct StoppedLiveLocation {
+    pub message: Message,
+    pub bot_request: StopMessageLiveLocationBody,
+}
+
 #[derive(Clone, Debug)]
 pub struct DeletedMessage {
     pub message: Message,
@@ -138,175 +341,704 @@ pub struct ForwardedMessage {
     pub bot_request: ForwardMessageBody,
 }
 
+/// An edit of a message that was sent via the bot in inline mode, identified by its
+/// `inline_message_id`.
+#[derive(Clone, Debug)]
+pub struct InlineMessageEdit {
+    /// The name of the edit method, like `EditMessageText`
+    pub method: String,
+    pub inline_message_id: String,
+    pub business_connection_id: Option<BusinessConnectionId>,
+}
+
 #[derive(Clone, Debug)]
 pub struct CopiedMessage {
     pub message_id: MessageId,
     pub bot_request: CopyMessageBody,
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct Responses {
-    /// All of the sent messages, including text, photo, audio, etc.
-    /// Be warned, editing or deleting messages do not affect this list!
-    pub sent_messages: Vec<Message>,
-
-    /// This has only messages that are text messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_text: Vec<SentMessageText>,
-
-    /// This has only messages that are photo messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_photo: Vec<SentMessagePhoto>,
-
-    /// This has only messages that are video messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_video: Vec<SentMessageVideo>,
-
-    /// This has only messages that are audio messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_audio: Vec<SentMessageAudio>,
-
-    /// This has only messages that are voice messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_voice: Vec<SentMessageVoice>,
-
-    /// This has only messages that are video note messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_video_note: Vec<SentMessageVideoNote>,
-
-    /// This has only messages that are document messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_document: Vec<SentMessageDocument>,
-
-    /// This has only messages that are animation messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_animation: Vec<SentMessageAnimation>,
-
-    /// This has only messages that are location messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_location: Vec<SentMessageLocation>,
-
-    /// This has only messages that are venue messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_venue: Vec<SentMessageVenue>,
-
-    /// This has only messages that are contact messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_contact: Vec<SentMessageContact>,
-
-    /// This has only messages that are dice messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_dice: Vec<SentMessageDice>,
-
-    /// This has only messages that are poll messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_poll: Vec<SentMessagePoll>,
-
-    /// This has only messages that are stickers, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_sticker: Vec<SentMessageSticker>,
-
-    /// This has only messages that are media group messages, sent by the bot.
-    /// The `.messages` field has the sent by bot messages, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_media_group: Vec<SentMediaGroup>,
-
-    /// This has only messages that are invoice messages, sent by the bot.
-    /// The `.message` field has the sent by bot message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub sent_messages_invoice: Vec<SentMessageInvoice>,
-
-    /// This has only edited by the bot text messages.
-    /// The `.message` field has the new edited message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub edited_messages_text: Vec<EditedMessageText>,
-
-    /// This has only edited by the bot caption messages.
-    /// The `.message` field has the new edited message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub edited_messages_caption: Vec<EditedMessageCaption>,
-
-    /// This has only messages whos reply markup was edited by the bot.
-    /// The `.message` field has the new edited message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub edited_messages_reply_markup: Vec<EditedMessageReplyMarkup>,
-
-    /// This has only messages which were deleted by the bot.
-    /// The `.message` field has the deleted message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub deleted_messages: Vec<DeletedMessage>,
-
-    /// This has only the requests that were sent to the fake server to forward messages.
-    /// The `.message` field has the forwarded message, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub forwarded_messages: Vec<ForwardedMessage>,
-
-    /// This has only the requests that were sent to the fake server to copy messages.
-    /// The `.message_id` field has the copied message id, and `.bot_request`
-    /// has the request that was sent to the fake server
-    pub copied_messages: Vec<CopiedMessage>,
-
-    /// This has only the requests that were sent to the fake server to answer callback queries.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub answered_callback_queries: Vec<AnswerCallbackQueryBody>,
-
-    /// This has only the requests that were sent to the fake server to pin messages.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub pinned_chat_messages: Vec<PinChatMessageBody>,
-
-    /// This has only the requests that were sent to the fake server to unpin messages.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub unpinned_chat_messages: Vec<UnpinChatMessageBody>,
-
-    /// This has only the requests that were sent to the fake server to unpin all messages.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub unpinned_all_chat_messages: Vec<UnpinAllChatMessagesBody>,
-
-    /// This has only the requests that were sent to the fake server to ban chat members.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub banned_chat_members: Vec<BanChatMemberBody>,
-
-    /// This has only the requests that were sent to the fake server to unban chat members.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub unbanned_chat_members: Vec<UnbanChatMemberBody>,
-
-    /// This has only the requests that were sent to the fake server to restrict chat members.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub restricted_chat_members: Vec<RestrictChatMemberBody>,
-
-    /// This has only the requests that were sent to the fake server to send chat actions.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub sent_chat_actions: Vec<SendChatActionBody>,
-
-    /// This has only the requests that were sent to the fake server to set message reactions.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub set_message_reaction: Vec<SetMessageReactionBody>,
-
-    /// This has only the requests that were sent to the fake server to set message reactions.
-    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
-    /// `.message` field.
-    pub set_my_commands: Vec<SetMyCommandsBody>,
+responses! {
+    #[derive(Clone, Debug, Default)]
+    pub struct Responses {
+        /// All of the sent messages, including text, photo, audio, etc.
+        /// Messages are stored in the order the server received the requests, so
+        /// the first `send_*` call of a handler is always first here.
+        /// Be warned, editing or deleting messages do not affect this list!
+        #[record(other)]
+        pub sent_messages: Vec<Message>,
+
+        /// This has only messages that are text messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_text: Vec<SentMessageText>,
+
+        /// This has only messages that are photo messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_photo: Vec<SentMessagePhoto>,
+
+        /// This has only messages that are video messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_video: Vec<SentMessageVideo>,
+
+        /// This has only messages that are audio messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_audio: Vec<SentMessageAudio>,
+
+        /// This has only messages that are voice messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_voice: Vec<SentMessageVoice>,
+
+        /// This has only messages that are video note messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_video_note: Vec<SentMessageVideoNote>,
+
+        /// This has only messages that are document messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_document: Vec<SentMessageDocument>,
+
+        /// This has only messages that are animation messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_animation: Vec<SentMessageAnimation>,
+
+        /// This has only messages that are location messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_location: Vec<SentMessageLocation>,
+
+        /// This has only messages that are venue messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_venue: Vec<SentMessageVenue>,
+
+        /// This has only messages that are contact messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_contact: Vec<SentMessageContact>,
+
+        /// This has only messages that are dice messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_dice: Vec<SentMessageDice>,
+
+        /// This has only messages that are poll messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_poll: Vec<SentMessagePoll>,
+
+        /// This has only messages that are stickers, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_sticker: Vec<SentMessageSticker>,
+
+        /// This has only messages that are media group messages, sent by the bot.
+        /// The `.messages` field has the sent by bot messages, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_media_group: Vec<SentMediaGroup>,
+
+        /// This has only messages that are paid media messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_paid_media: Vec<SentMessagePaidMedia>,
+
+        /// This has only messages that are invoice messages, sent by the bot.
+        /// The `.message` field has the sent by bot message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(sent)]
+        pub sent_messages_invoice: Vec<SentMessageInvoice>,
+
+        /// This has only edited by the bot text messages.
+        /// The `.message` field has the new edited message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(edit)]
+        pub edited_messages_text: Vec<EditedMessageText>,
+
+        /// This has only edited by the bot caption messages.
+        /// The `.message` field has the new edited message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(edit)]
+        pub edited_messages_caption: Vec<EditedMessageCaption>,
+
+        /// This has only messages whos reply markup was edited by the bot.
+        /// The `.message` field has the new edited message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(edit)]
+        pub edited_messages_reply_markup: Vec<EditedMessageReplyMarkup>,
+
+        /// This has only live location messages edited by the bot.
+        /// The `.message` field has the new edited message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(edit)]
+        pub edited_messages_live_location: Vec<EditedMessageLiveLocation>,
+
+        /// This has only live location messages that were stopped by the bot.
+        /// The `.message` field has the stopped message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(call)]
+        pub stopped_live_locations: Vec<StoppedLiveLocation>,
+
+        /// This has only the edits of inline messages, that are sent via the bot in inline mode.
+        /// The fake server doesn't store inline messages, so Telegram only returns `true` and
+        /// there is no `.message` field.
+        #[record(edit)]
+        pub inline_message_edits: Vec<InlineMessageEdit>,
+
+        /// This has the copies of the channel posts sent by the bot, that were automatically
+        /// forwarded to the linked discussion group. Link the chats with `MockBot::link_chats`.
+        #[record(other)]
+        pub automatic_forwards: Vec<Message>,
+
+        /// This has only messages which were deleted by the bot.
+        /// The `.message` field has the deleted message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(call)]
+        pub deleted_messages: Vec<DeletedMessage>,
+
+        /// This has only the requests that were sent to the fake server to forward messages.
+        /// The `.message` field has the forwarded message, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(call)]
+        pub forwarded_messages: Vec<ForwardedMessage>,
+
+        /// This has only the requests that were sent to the fake server to copy messages.
+        /// The `.message_id` field has the copied message id, and `.bot_request`
+        /// has the request that was sent to the fake server
+        #[record(call)]
+        pub copied_messages: Vec<CopiedMessage>,
+
+        /// This has only the requests that were sent to the fake server to answer callback queries.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub answered_callback_queries: Vec<AnswerCallbackQueryBody>,
+
+        /// This has only the requests that were sent to the fake server to pin messages.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub pinned_chat_messages: Vec<PinChatMessageBody>,
+
+        /// This has only the requests that were sent to the fake server to unpin messages.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub unpinned_chat_messages: Vec<UnpinChatMessageBody>,
+
+        /// This has only the requests that were sent to the fake server to unpin all messages.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub unpinned_all_chat_messages: Vec<UnpinAllChatMessagesBody>,
+
+        /// This has only the requests that were sent to the fake server to ban chat members.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub banned_chat_members: Vec<BanChatMemberBody>,
+
+        /// This has only the requests that were sent to the fake server to unban chat members.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub unbanned_chat_members: Vec<UnbanChatMemberBody>,
+
+        /// This has only the requests that were sent to the fake server to restrict chat members.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub restricted_chat_members: Vec<RestrictChatMemberBody>,
+
+        /// This has only the requests that were sent to the fake server to set chat permissions.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub set_chat_permissions: Vec<SetChatPermissionsBody>,
+
+        /// This has only the requests that were sent to the fake server to set chat sticker sets.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub set_chat_sticker_sets: Vec<SetChatStickerSetBody>,
+
+        /// This has only the requests that were sent to the fake server to delete chat sticker
+        /// sets. Telegram doesn't return anything, because there isn't anything to return, so
+        /// there is no `.message` field.
+        #[record(call)]
+        pub deleted_chat_sticker_sets: Vec<DeleteChatStickerSetBody>,
+
+        /// This has only the requests that were sent to the fake server to send chat actions.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub sent_chat_actions: Vec<SendChatActionBody>,
+
+        /// This has only the requests that were sent to the fake server to set message reactions.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub set_message_reaction: Vec<SetMessageReactionBody>,
+
+        /// This has only the requests that were sent to the fake server to set message reactions.
+        /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+        /// `.message` field.
+        #[record(call)]
+        pub set_my_commands: Vec<SetMyCommandsBody>,
+
+        /// This has the bodies of all requests, exactly as teloxide sent them. It is empty unless
+        /// raw requests are captured with `MockBot::capture_raw_requests(true)`.
+        #[record(other)]
+        pub raw_requests: Vec<RawRequest>,
+
+        /// The names of all methods the bot called, like `SendMessage`, in the order the fake
+        /// server handled them. Includes the requests that failed and the ones that only read data.
+        #[record(other)]
+        pub endpoint_calls: Vec<String>,
+    }
+}
+
+impl Responses {
+    /// Returns the author of the last sent message. Every message sent through the fake server
+    /// is authored by the bot, so this is the bot user if anything was sent.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_sender(), None);
+    /// ```
+    ///
+    pub fn last_sender(&self) -> Option<User> {
+        self.sent_messages.last()?.from.clone()
+    }
+
+    /// Returns the id of the message the last sent text message replied to, taken from the
+    /// `reply_parameters` of its request.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_reply_to_message_id(), None);
+    /// ```
+    ///
+    pub fn last_reply_to_message_id(&self) -> Option<i32> {
+        let sent = self.sent_messages_text.last()?;
+        Some(sent.bot_request.reply_parameters.as_ref()?.message_id.0)
+    }
+
+    /// Returns the `reply_markup` of the last `send_*` request that had one, exactly as teloxide
+    /// serialized it. Requires `MockBot::capture_raw_requests(true)`.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_raw_reply_markup(), None);
+    /// ```
+    ///
+    pub fn last_raw_reply_markup(&self) -> Option<String> {
+        self.raw_requests
+            .iter()
+            .rev()
+            .filter(|request| request.method.starts_with("Send"))
+            .find_map(|request| request.field("reply_markup"))
+    }
+
+    /// Returns the web app button from the inline keyboard of the latest sent message that has
+    /// one, if there is any.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert!(responses.find_web_app_button().is_none());
+    /// ```
+    ///
+    pub fn find_web_app_button(&self) -> Option<&InlineKeyboardButton> {
+        self.sent_messages
+            .iter()
+            .rev()
+            .filter_map(|message| message.reply_markup())
+            .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+            .find(|button| matches!(button.kind, InlineKeyboardButtonKind::WebApp(_)))
+    }
+
+    /// Returns the `input_field_placeholder` of the reply keyboard the latest sent message was
+    /// sent with. `None` if that message had no reply keyboard, or the keyboard had no placeholder.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_input_field_placeholder(), None);
+    /// ```
+    ///
+    pub fn last_input_field_placeholder(&self) -> Option<&str> {
+        match self.last_reply_markup()? {
+            ReplyMarkup::Keyboard(keyboard) if !keyboard.input_field_placeholder.is_empty() => {
+                Some(&keyboard.input_field_placeholder)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `is_persistent` flag of the reply keyboard the latest sent message was sent
+    /// with. `None` if that message had no reply keyboard.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_keyboard_is_persistent(), None);
+    /// ```
+    ///
+    pub fn last_keyboard_is_persistent(&self) -> Option<bool> {
+        match self.last_reply_markup()? {
+            ReplyMarkup::Keyboard(keyboard) => Some(keyboard.is_persistent),
+            _ => None,
+        }
+    }
+
+    /// Returns the quote the latest sent message was replying with, with its entities, taken
+    /// from the reply parameters of the request. The entities are parsed from the quote if it
+    /// was sent with `quote_parse_mode`. `None` if the message wasn't replying with a quote.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_quote(), None);
+    /// ```
+    ///
+    pub fn last_quote(&self) -> Option<(String, Vec<MessageEntity>)> {
+        let reply_parameters = self.last_send_options()?.1?;
+        let quote = reply_parameters.quote.as_deref()?;
+        // The send routes reject the quotes that can't be parsed, so this only falls back for
+        // the responses that were made by hand
+        quote_with_entities(reply_parameters).unwrap_or_else(|_| Some((quote.to_owned(), vec![])))
+    }
+
+    /// Returns the `quote_position` the latest sent message was replying with, which is where
+    /// the quote starts in the replied message, in UTF-16 code units. `None` if the message had
+    /// no quote position in its reply parameters.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_quote_position(), None);
+    /// ```
+    ///
+    pub fn last_quote_position(&self) -> Option<u32> {
+        self.last_send_options()?.1?.quote_position
+    }
+
+    /// Returns the reply markup from the request of the latest sent message
+    fn last_reply_markup(&self) -> Option<&ReplyMarkup> {
+        self.last_send_options()?.0
+    }
+
+    /// Returns the reply markup and the reply parameters from the request of the latest sent
+    /// message
+    fn last_send_options(&self) -> Option<(Option<&ReplyMarkup>, Option<&ReplyParameters>)> {
+        let last = self.sent_messages.last()?;
+        let sent = self.sent_records().find(|sent| {
+            sent.messages()
+                .iter()
+                .any(|message| message.id == last.id && message.chat.id == last.chat.id)
+        })?;
+        Some((sent.reply_markup(), sent.reply_parameters()))
+    }
+
+    /// Returns `true` if the latest callback query answer was silent, with no text and no alert,
+    /// so the user only sees the loading stop. `false` if no callback query was answered.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert!(!responses.last_callback_was_silent());
+    /// ```
+    ///
+    pub fn last_callback_was_silent(&self) -> bool {
+        self.answered_callback_queries.last().is_some_and(|answer| {
+            answer.text.as_deref().unwrap_or_default().is_empty() && answer.show_alert != Some(true)
+        })
+    }
+
+    /// Returns the actions the bot sent with `send_chat_action`, in order, as teloxide's
+    /// `ChatAction`. Actions that teloxide doesn't know are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert!(responses.chat_actions_typed().is_empty());
+    /// ```
+    ///
+    pub fn chat_actions_typed(&self) -> Vec<ChatAction> {
+        self.sent_chat_actions
+            .iter()
+            .filter_map(|sent| {
+                serde_json::from_value(serde_json::Value::String(sent.action.clone())).ok()
+            })
+            .collect()
+    }
+
+    /// Returns the currency and the total amount of the latest invoice the bot sent, the amount
+    /// being in the smallest units of the currency, like cents. `None` if no invoice was sent.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.last_invoice_total(), None);
+    /// ```
+    ///
+    pub fn last_invoice_total(&self) -> Option<(String, u32)> {
+        let invoice = self.sent_messages_invoice.last()?.message.invoice()?;
+        Some((invoice.currency.clone(), invoice.total_amount))
+    }
+
+    /// Returns the public `https://t.me/<username>/<id>` link of a sent message, or `None` if
+    /// there is no such message, or its chat isn't a supergroup or a channel with a username.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.message_link(1), None);
+    /// ```
+    ///
+    pub fn message_link(&self, message_id: i32) -> Option<String> {
+        let message = self
+            .sent_messages
+            .iter()
+            .find(|message| message.id.0 == message_id)?;
+        if !message.chat.is_channel() && !message.chat.is_supergroup() {
+            return None;
+        }
+        let username = message.chat.username()?;
+        Some(format!("https://t.me/{username}/{message_id}"))
+    }
+
+    /// Returns the id of the effect a sent message was sent with, or `None` if there is no such
+    /// message, or it was sent without an effect. Dice messages have no `effect_id` in teloxide,
+    /// so for them it is taken from the `send_dice` request.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.effect_id(1), None);
+    /// ```
+    ///
+    pub fn effect_id(&self, message_id: i32) -> Option<&EffectId> {
+        if let Some(dice) = self
+            .sent_messages_dice
+            .iter()
+            .find(|sent| sent.message.id.0 == message_id)
+        {
+            return dice.bot_request.message_effect_id.as_ref();
+        }
+        self.sent_messages
+            .iter()
+            .find(|message| message.id.0 == message_id)?
+            .effect_id()
+    }
+
+    /// Returns how many requests the bot made to the recorded methods, of any kind.
+    ///
+    /// A media group counts as one request, even though it sends several messages. Requests
+    /// that only read data, like `get_me` or `get_chat`, aren't recorded and aren't counted.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.total_calls(), 0);
+    /// ```
+    ///
+    pub fn total_calls(&self) -> usize {
+        self.recorded_calls()
+    }
+
+    /// Returns `true` if the bot made no recorded requests at all, see [`total_calls`].
+    ///
+    /// [`total_calls`]: crate::Responses::total_calls
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert!(responses.is_empty());
+    /// ```
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.total_calls() == 0
+    }
+
+    /// Returns how many edits the bot made, counting the text, caption, reply markup and live
+    /// location edits of the stored messages, and all of the inline message edits.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.total_edits(), 0);
+    /// ```
+    ///
+    pub fn total_edits(&self) -> usize {
+        self.recorded_edits()
+    }
+
+    /// Returns how many reactions the bot set with all of its `set_message_reaction` calls. A call
+    /// that removes the reactions of a message adds nothing.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.total_reactions(), 0);
+    /// ```
+    ///
+    pub fn total_reactions(&self) -> usize {
+        self.set_message_reaction
+            .iter()
+            .map(|call| call.reaction.as_ref().map_or(0, Vec::len))
+            .sum()
+    }
+
+    /// Returns how many times the bot called an endpoint, like `/SendMessage`. The leading slash
+    /// is optional.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.call_count("/SendMessage"), 0);
+    /// ```
+    ///
+    pub fn call_count(&self, endpoint: &str) -> usize {
+        let endpoint = endpoint.trim_start_matches('/');
+        self.endpoint_calls
+            .iter()
+            .filter(|call| call.as_str() == endpoint)
+            .count()
+    }
+
+    /// Returns the messages the bot sent to a thread, like a forum topic, in the order they were
+    /// sent.
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::{MessageId, ThreadId};
+    ///
+    /// let responses = teremock::Responses::default();
+    /// assert!(responses.messages_in_thread(ThreadId(MessageId(7))).is_empty());
+    /// ```
+    ///
+    pub fn messages_in_thread(&self, thread_id: ThreadId) -> Vec<&Message> {
+        self.sent_messages
+            .iter()
+            .filter(|message| message.thread_id == Some(thread_id))
+            .collect()
+    }
+
+    /// Returns a view over the sent messages, that can be narrowed down with chainable filters.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert!(responses.view().to(1234).texts().is_empty());
+    /// ```
+    ///
+    pub fn view(&self) -> ResponsesView<'_> {
+        ResponsesView {
+            messages: self.sent_messages.iter().collect(),
+        }
+    }
+
+    /// Returns a human readable dump of everything the bot did, meant to be printed when a test
+    /// fails. Every sent message is listed with its id, chat and text (or caption), followed by
+    /// the amount of requests of every other kind that was recorded.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teremock::Responses::default();
+    /// assert_eq!(responses.summary(), "sent_messages: 0");
+    /// ```
+    ///
+    pub fn summary(&self) -> String {
+        let mut summary = format!("sent_messages: {}", self.sent_messages.len());
+        for message in &self.sent_messages {
+            let content = match message.text().or(message.caption()) {
+                Some(text) => format!("{text:?}"),
+                None => "<no text>".to_string(),
+            };
+            let _ = write!(
+                summary,
+                "\n  #{} in chat {}: {}",
+                message.id.0, message.chat.id.0, content
+            );
+        }
+
+        // The automatic forwards aren't requests of the bot, but they are still worth seeing
+        let counts = self
+            .recorded_counts()
+            .into_iter()
+            .chain([("automatic_forwards", self.automatic_forwards.len())]);
+        for (name, count) in counts.filter(|(_, count)| *count > 0) {
+            let _ = write!(summary, "\n{name}: {count}");
+        }
+
+        summary
+    }
+}
+
+/// A filtered view over [`Responses::sent_messages`], made with [`Responses::view`].
+///
+/// Every filter keeps the order in which the messages were sent.
+#[derive(Clone, Debug)]
+pub struct ResponsesView<'a> {
+    messages: Vec<&'a Message>,
+}
+
+impl<'a> ResponsesView<'a> {
+    /// Keeps only the messages sent to the chat
+    pub fn to(mut self, chat_id: impl IntoChatId) -> Self {
+        let chat_id = chat_id.into_chat_id();
+        self.messages.retain(|message| message.chat.id == chat_id);
+        self
+    }
+
+    /// Keeps only the photo messages
+    pub fn photos(mut self) -> Self {
+        self.messages.retain(|message| message.photo().is_some());
+        self
+    }
+
+    /// Keeps only the messages with this exact caption
+    pub fn with_caption(mut self, caption: &str) -> Self {
+        self.messages
+            .retain(|message| message.caption() == Some(caption));
+        self
+    }
+
+    /// Returns the texts of the text messages in the view
+    pub fn texts(&self) -> Vec<&'a str> {
+        self.messages
+            .iter()
+            .filter_map(|message| message.text())
+            .collect()
+    }
+
+    /// Returns the messages in the view
+    pub fn messages(&self) -> &[&'a Message] {
+        &self.messages
+    }
 }
@@ -2,13 +2,17 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
+use teloxide::types::BusinessConnectionId;
 
 use super::{
-    common::{lock_state, RouteResult},
+    common::{lock_state, RouteError, RouteResult},
     make_telegram_result,
 };
 use crate::state::State;
 
+/// The longest callback answer text Telegram accepts, in characters
+const MAX_TEXT_LENGTH: usize = 200;
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AnswerCallbackQueryBody {
     pub callback_query_id: String,
@@ -16,6 +20,9 @@ pub struct AnswerCallbackQueryBody {
     pub show_alert: Option<bool>,
     pub url: Option<String>,
     pub cache_time: Option<i32>,
+    /// Teloxide doesn't send this yet, but bots making raw requests for callbacks from a
+    /// business connection do
+    pub business_connection_id: Option<BusinessConnectionId>,
 }
 
 pub async fn answer_callback_query(
@@ -23,6 +30,13 @@ pub async fn answer_callback_query(
     body: web::Json<AnswerCallbackQueryBody>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
+    if body
+        .text
+        .as_ref()
+        .is_some_and(|text| text.chars().count() > MAX_TEXT_LENGTH)
+    {
+        return Err(RouteError::bad_request("Bad Request: MESSAGE_TOO_LONG"));
+    }
     lock.responses
         .answered_callback_queries
         .push(body.into_inner());
@@ -1,7 +1,9 @@
 use std::sync::Mutex;
 
 use actix_web::web;
+use chrono::DateTime;
 use serde::Deserialize;
+use teloxide::types::{Banned, ChatMemberKind, UntilDate};
 
 use super::{
     common::{lock_state, RouteResult},
@@ -37,6 +39,18 @@ pub async fn ban_chat_member(
             lock.messages.delete_message(id);
         }
     }
+    // Like Telegram, a date that isn't in the future bans the user forever
+    let until_date = match body
+        .until_date
+        .and_then(|date| DateTime::from_timestamp(date, 0))
+    {
+        Some(date) if date > lock.now() => UntilDate::Date(date),
+        _ => UntilDate::Forever,
+    };
+    lock.chat_members.insert(
+        (chat_id, body.user_id),
+        ChatMemberKind::Banned(Banned { until_date }),
+    );
     lock.responses.banned_chat_members.push(body.into_inner());
 
     Ok(make_telegram_result(true))
@@ -5,9 +5,11 @@ use std::sync::{Mutex, MutexGuard, PoisonError};
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use rand::distr::{Alphanumeric, SampleString};
 use teloxide::types::{
-    FileId, FileUniqueId, InlineKeyboardMarkup, Message, ReplyMarkup, ReplyParameters, User,
+    Chat, ExternalReplyInfo, ExternalReplyInfoKind, FileId, FileUniqueId, InlineKeyboardMarkup,
+    Message, MessageId, MessageOrigin, Recipient, ReplyMarkup, ReplyParameters, ThreadId, User,
 };
 
+use super::entities::quote_with_entities;
 use crate::state::State;
 
 /// Default chat ID used when a text username is provided instead of a numeric ID.
@@ -144,13 +146,20 @@ pub fn extract_inline_keyboard(markup: Option<&ReplyMarkup>) -> Option<InlineKey
 /// - Looking up and setting `reply_to_message` if reply_parameters provided
 /// - Setting `reply_markup` if it's an inline keyboard
 ///
-/// Returns the reply_to_message if found, or an error if the referenced message doesn't exist.
+/// Returns the reply_to_message if found, or an error if the referenced message doesn't exist,
+/// its id isn't positive, or the quote can't be parsed.
 pub fn setup_reply_to_message(
     lock: &MutexGuard<'_, State>,
     reply_parameters: Option<&ReplyParameters>,
 ) -> Result<Option<Box<Message>>, RouteError> {
     if let Some(params) = reply_parameters {
         let message_id = params.message_id.0;
+        if message_id <= 0 {
+            return Err(RouteError::bad_request(&format!(
+                "Bad Request: message_id in reply_parameters must be positive, got {message_id}"
+            )));
+        }
+        quote_with_entities(params)?;
         match lock.messages.get_message(message_id) {
             Some(msg) => Ok(Some(Box::new(msg))),
             None => Err(RouteError::bad_request(
@@ -182,27 +191,154 @@ pub struct MessageSetup {
     pub from: Option<User>,
     pub has_protected_content: bool,
     pub reply_to_message: Option<Box<Message>>,
+    pub external_reply: Option<ExternalReplyInfo>,
     pub reply_markup: Option<InlineKeyboardMarkup>,
+    pub thread_id: Option<ThreadId>,
+    pub is_topic_message: bool,
 }
 
 impl MessageSetup {
     /// Creates a new MessageSetup from common request fields.
     ///
+    /// The reply is set up with [`setup_reply`].
+    ///
     /// Returns an error if reply_parameters references a non-existent message.
     pub fn new(
         me_user: &User,
+        chat: &Chat,
+        message_thread_id: Option<i64>,
         protect_content: Option<bool>,
         reply_parameters: Option<&ReplyParameters>,
         reply_markup: Option<&ReplyMarkup>,
         state_lock: &MutexGuard<'_, State>,
     ) -> Result<Self, RouteError> {
-        let reply_to_message = setup_reply_to_message(state_lock, reply_parameters)?;
+        let (reply_to_message, external_reply) = setup_reply(state_lock, chat, reply_parameters)?;
 
         Ok(Self {
             from: Some(me_user.clone()),
             has_protected_content: protect_content.unwrap_or(false),
             reply_to_message,
+            external_reply,
             reply_markup: extract_inline_keyboard(reply_markup),
+            thread_id: message_thread_id.map(|thread_id| ThreadId(MessageId(thread_id as i32))),
+            is_topic_message: message_thread_id.is_some(),
         })
     }
 }
+
+/// Sets up the reply of a message sent to `chat`, as its `reply_to_message` and
+/// `external_reply`.
+///
+/// If reply_parameters point to a message in another chat than `chat`, the reply is set as
+/// `external_reply` instead of `reply_to_message`, like Telegram does. teloxide has no
+/// `external_reply` for messages without media, so a reply to a plain text message from
+/// another chat is left with neither.
+///
+/// Returns an error if reply_parameters references a non-existent message.
+pub fn setup_reply(
+    lock: &MutexGuard<'_, State>,
+    chat: &Chat,
+    reply_parameters: Option<&ReplyParameters>,
+) -> Result<(Option<Box<Message>>, Option<ExternalReplyInfo>), RouteError> {
+    let reply_to_message = setup_reply_to_message(lock, reply_parameters)?;
+    match (reply_to_message, reply_parameters) {
+        (Some(reply), Some(params)) if is_other_chat(params.chat_id.as_ref(), chat) => {
+            Ok((None, external_reply_info(&reply)))
+        }
+        (reply_to_message, _) => Ok((reply_to_message, None)),
+    }
+}
+
+/// Checks if the `chat_id` of reply parameters points to a chat other than `chat`
+fn is_other_chat(reply_chat_id: Option<&Recipient>, chat: &Chat) -> bool {
+    match reply_chat_id {
+        Some(Recipient::Id(chat_id)) => *chat_id != chat.id,
+        Some(Recipient::ChannelUsername(username)) => {
+            chat.username() != Some(username.trim_start_matches('@'))
+        }
+        None => false,
+    }
+}
+
+/// Describes a message from another chat the way `external_reply` does.
+///
+/// Returns `None` for messages without media, because teloxide can't represent those.
+fn external_reply_info(message: &Message) -> Option<ExternalReplyInfo> {
+    let kind = if let Some(animation) = message.animation() {
+        ExternalReplyInfoKind::Animation(animation.clone())
+    } else if let Some(audio) = message.audio() {
+        ExternalReplyInfoKind::Audio(audio.clone())
+    } else if let Some(contact) = message.contact() {
+        ExternalReplyInfoKind::Contact(contact.clone())
+    } else if let Some(dice) = message.dice() {
+        ExternalReplyInfoKind::Dice(dice.clone())
+    } else if let Some(document) = message.document() {
+        ExternalReplyInfoKind::Document(document.clone())
+    } else if let Some(venue) = message.venue() {
+        ExternalReplyInfoKind::Venue(venue.clone())
+    } else if let Some(location) = message.location() {
+        ExternalReplyInfoKind::Location(*location)
+    } else if let Some(photo) = message.photo() {
+        ExternalReplyInfoKind::Photo(photo.to_vec())
+    } else if let Some(poll) = message.poll() {
+        ExternalReplyInfoKind::Poll(poll.clone())
+    } else if let Some(sticker) = message.sticker() {
+        ExternalReplyInfoKind::Sticker(sticker.clone())
+    } else if let Some(video) = message.video() {
+        ExternalReplyInfoKind::Video(video.clone())
+    } else if let Some(video_note) = message.video_note() {
+        ExternalReplyInfoKind::VideoNote(video_note.clone())
+    } else if let Some(voice) = message.voice() {
+        ExternalReplyInfoKind::Voice(voice.clone())
+    } else if let Some(invoice) = message.invoice() {
+        ExternalReplyInfoKind::Invoice(invoice.clone())
+    } else if let Some(game) = message.game() {
+        ExternalReplyInfoKind::Game(game.clone())
+    } else if let Some(story) = message.story() {
+        ExternalReplyInfoKind::Story(story.clone())
+    } else {
+        return None;
+    };
+
+    // The chat and the message id are only shown for public chats
+    let is_public = message.chat.is_supergroup() || message.chat.is_channel();
+
+    Some(ExternalReplyInfo {
+        origin: message_origin(message),
+        chat: is_public.then(|| message.chat.clone()),
+        message_id: is_public.then_some(message.id),
+        link_preview_options: None,
+        has_media_spoiler: message.has_media_spoiler(),
+        kind,
+    })
+}
+
+/// Describes where a message originally came from, the way `forward_origin` and
+/// `external_reply` do
+pub fn message_origin(message: &Message) -> MessageOrigin {
+    if message.chat.is_channel() {
+        MessageOrigin::Channel {
+            date: message.date,
+            chat: message.chat.clone(),
+            message_id: message.id,
+            author_signature: None,
+        }
+    } else if let Some(sender_chat) = &message.sender_chat {
+        MessageOrigin::Chat {
+            date: message.date,
+            sender_chat: sender_chat.clone(),
+            author_signature: None,
+        }
+    } else if let Some(user) = &message.from {
+        MessageOrigin::User {
+            date: message.date,
+            sender_user: user.clone(),
+        }
+    } else {
+        // This is probably unreachable.
+        MessageOrigin::HiddenUser {
+            date: message.date,
+            sender_user_name: "Unknown user".to_string(),
+        }
+    }
+}
@@ -4,12 +4,14 @@ use actix_web::web;
 use serde::Deserialize;
 use serde_json::json;
 use teloxide::types::{
-    Me, MediaAnimation, MediaAudio, MediaDocument, MediaKind, MediaPhoto, MediaVideo, MediaVoice,
-    MessageEntity, MessageId, MessageKind, ParseMode, ReplyMarkup,
+    EffectId, MediaAnimation, MediaAudio, MediaDocument, MediaKind, MediaPhoto, MediaVideo,
+    MediaVoice, MessageEntity, MessageId, MessageKind, ParseMode, ReplyMarkup, ReplyParameters,
+    ThreadId,
 };
 
 use super::{
-    common::{lock_state, RouteError, RouteResult},
+    common::{lock_state, setup_reply, RouteError, RouteResult},
+    entities::text_with_entities,
     make_telegram_result, BodyChatId,
 };
 use crate::{
@@ -29,31 +31,38 @@ pub struct CopyMessageBody {
     pub show_caption_above_media: Option<bool>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub message_effect_id: Option<EffectId>,
+    pub reply_parameters: Option<ReplyParameters>,
     pub reply_markup: Option<ReplyMarkup>,
 }
 
 pub async fn copy_message(
     body: web::Json<CopyMessageBody>,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     check_if_message_exists!(lock, body.message_id, result);
 
     let mut message = lock
         .messages
         .get_message(body.message_id)
         .ok_or_else(|| RouteError::bad_request("Message not found"))?;
+    let (reply_to_message, external_reply) =
+        setup_reply(&lock, &chat, body.reply_parameters.as_ref())?;
     message.chat = chat;
-    message.from = Some(me.user.clone());
+    message.from = Some(lock.me.user.clone());
 
     if let MessageKind::Common(ref mut common) = message.kind {
         common.forward_origin = None;
-        common.external_reply = None;
+        common.reply_to_message = reply_to_message;
+        common.external_reply = external_reply;
+        // Copies are sent by the bot right now, so they don't keep the effect or the offline flag
+        common.effect_id = body.message_effect_id.clone();
+        common.is_from_offline = false;
 
         // Handle show_caption_above_media for supported media types
-        let show_above = body.show_caption_above_media.unwrap_or(false);
+        let show_above = body.show_caption_above_media;
 
         match common.media_kind {
             MediaKind::Animation(MediaAnimation {
@@ -62,17 +71,15 @@ pub async fn copy_message(
                 ref mut show_caption_above_media,
                 ..
             }) => {
-                *caption = body.caption.clone();
-                *caption_entities = body.caption_entities.clone().unwrap_or_default();
-                *show_caption_above_media = show_above;
+                override_caption(&body, caption, caption_entities)?;
+                *show_caption_above_media = show_above.unwrap_or(*show_caption_above_media);
             }
             MediaKind::Audio(MediaAudio {
                 ref mut caption,
                 ref mut caption_entities,
                 ..
             }) => {
-                *caption = body.caption.clone();
-                *caption_entities = body.caption_entities.clone().unwrap_or_default();
+                override_caption(&body, caption, caption_entities)?;
                 // Audio doesn't support show_caption_above_media
             }
             MediaKind::Document(MediaDocument {
@@ -80,8 +87,7 @@ pub async fn copy_message(
                 ref mut caption_entities,
                 ..
             }) => {
-                *caption = body.caption.clone();
-                *caption_entities = body.caption_entities.clone().unwrap_or_default();
+                override_caption(&body, caption, caption_entities)?;
                 // Document doesn't support show_caption_above_media
             }
             MediaKind::Photo(MediaPhoto {
@@ -90,9 +96,8 @@ pub async fn copy_message(
                 ref mut show_caption_above_media,
                 ..
             }) => {
-                *caption = body.caption.clone();
-                *caption_entities = body.caption_entities.clone().unwrap_or_default();
-                *show_caption_above_media = show_above;
+                override_caption(&body, caption, caption_entities)?;
+                *show_caption_above_media = show_above.unwrap_or(*show_caption_above_media);
             }
             MediaKind::Video(MediaVideo {
                 ref mut caption,
@@ -100,17 +105,15 @@ pub async fn copy_message(
                 ref mut show_caption_above_media,
                 ..
             }) => {
-                *caption = body.caption.clone();
-                *caption_entities = body.caption_entities.clone().unwrap_or_default();
-                *show_caption_above_media = show_above;
+                override_caption(&body, caption, caption_entities)?;
+                *show_caption_above_media = show_above.unwrap_or(*show_caption_above_media);
             }
             MediaKind::Voice(MediaVoice {
                 ref mut caption,
                 ref mut caption_entities,
                 ..
             }) => {
-                *caption = body.caption.clone();
-                *caption_entities = body.caption_entities.clone().unwrap_or_default();
+                override_caption(&body, caption, caption_entities)?;
                 // Voice doesn't support show_caption_above_media
             }
             _ => {}
@@ -124,10 +127,15 @@ pub async fn copy_message(
 
     let last_id = lock.messages.max_message_id();
     message.id = MessageId(last_id + 1);
-    message.chat = body.chat_id.chat();
-    let message = lock.messages.add_message(message);
+    message.chat = body.chat_id.chat(&lock);
+    message.thread_id = body
+        .message_thread_id
+        .map(|thread_id| ThreadId(MessageId(thread_id as i32)));
+    message.is_topic_message = message.thread_id.is_some();
+    let message = lock.add_sent_message(message);
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.copied_messages.push(CopiedMessage {
         message_id: message.id,
         bot_request: body.into_inner(),
@@ -137,3 +145,20 @@ pub async fn copy_message(
         "message_id": message.id.0
     })))
 }
+
+/// Replaces the caption of the copied message, if the bot sent a new one.
+/// Otherwise the original caption and its entities are kept, like Telegram does.
+fn override_caption(
+    body: &CopyMessageBody,
+    caption: &mut Option<String>,
+    caption_entities: &mut Vec<MessageEntity>,
+) -> Result<(), RouteError> {
+    if body.caption.is_some() {
+        (*caption, *caption_entities) = text_with_entities(
+            body.caption.as_deref(),
+            body.parse_mode,
+            body.caption_entities.as_deref(),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+
+use super::{
+    common::{lock_state, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::state::State;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DeleteChatStickerSetBody {
+    pub chat_id: BodyChatId,
+}
+
+pub async fn delete_chat_sticker_set(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<DeleteChatStickerSetBody>,
+) -> RouteResult {
+    let mut lock = lock_state(&state)?;
+    lock.chat_sticker_sets.remove(&body.chat_id.id());
+    lock.responses
+        .deleted_chat_sticker_sets
+        .push(body.into_inner());
+    Ok(make_telegram_result(true))
+}
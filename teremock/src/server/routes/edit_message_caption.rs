@@ -1,12 +1,17 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
+use actix_web::web;
 use serde::Deserialize;
 use teloxide::types::{BusinessConnectionId, MessageEntity, ParseMode, ReplyMarkup};
 
-use super::{check_if_message_exists, BodyChatId};
+use super::{
+    check_if_message_exists,
+    common::{lock_state, RouteError, RouteResult},
+    entities::text_with_entities,
+    BodyChatId,
+};
 use crate::{
-    server::{routes::make_telegram_result, EditedMessageCaption},
+    server::{routes::make_telegram_result, EditedMessageCaption, InlineMessageEdit},
     state::State,
 };
 
@@ -26,27 +31,34 @@ pub struct EditMessageCaptionBody {
 pub async fn edit_message_caption(
     state: web::Data<Mutex<State>>,
     body: web::Json<EditMessageCaptionBody>,
-) -> impl Responder {
+) -> RouteResult {
     match (
         body.chat_id.clone(),
         body.message_id,
         body.inline_message_id.clone(),
     ) {
         (Some(_), Some(message_id), None) => {
-            let mut lock = state.lock().unwrap();
-            check_if_message_exists!(lock, message_id);
+            let mut lock = lock_state(&state)?;
+            check_if_message_exists!(lock, message_id, result);
+            let (caption, caption_entities) = text_with_entities(
+                Some(&body.caption),
+                body.parse_mode,
+                body.caption_entities.as_deref(),
+            )?;
+            lock.messages
+                .edit_message_field(message_id, "caption", caption);
             lock.messages
-                .edit_message_field(message_id, "caption", body.caption.clone());
-            lock.messages.edit_message_field(
-                message_id,
-                "caption_entities",
-                body.caption_entities.clone().unwrap_or_default(),
-            );
-            lock.messages.edit_message_field(
-                message_id,
-                "show_caption_above_media",
-                body.show_caption_above_media.unwrap_or(false),
-            );
+                .edit_message_field(message_id, "caption_entities", caption_entities);
+            // The caption stays where it was, unless the bot moves it
+            if let Some(show_caption_above_media) = body.show_caption_above_media {
+                lock.messages.edit_message_field(
+                    message_id,
+                    "show_caption_above_media",
+                    show_caption_above_media,
+                );
+            }
+
+            lock.set_edit_date(message_id);
 
             let message = lock
                 .messages
@@ -60,9 +72,20 @@ pub async fn edit_message_caption(
                     bot_request: body.into_inner(),
                 });
 
-            make_telegram_result(message)
+            Ok(make_telegram_result(message))
+        }
+        // Inline messages aren't stored, so the edit is only recorded
+        (None, None, Some(inline_message_id)) => {
+            let mut lock = lock_state(&state)?;
+            lock.responses.inline_message_edits.push(InlineMessageEdit {
+                method: "EditMessageCaption".to_owned(),
+                inline_message_id,
+                business_connection_id: body.business_connection_id.clone(),
+            });
+            Ok(make_telegram_result(true))
         }
-        (None, None, Some(_)) => make_telegram_result(true),
-        _ => ErrorBadRequest("No message_id or inline_message_id were provided").into(),
+        _ => Err(RouteError::bad_request(
+            "No message_id or inline_message_id were provided",
+        )),
     }
 }
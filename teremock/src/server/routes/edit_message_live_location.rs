@@ -0,0 +1,111 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+use teloxide::{
+    types::{BusinessConnectionId, LivePeriod, ReplyMarkup},
+    ApiError,
+};
+
+use super::{
+    common::{lock_state, RouteError, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::{
+    server::{EditedMessageLiveLocation, InlineMessageEdit},
+    state::State,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EditMessageLiveLocationBody {
+    pub chat_id: Option<BodyChatId>,
+    pub message_id: Option<i32>,
+    pub inline_message_id: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub live_period: Option<LivePeriod>,
+    pub horizontal_accuracy: Option<f64>,
+    pub heading: Option<u16>,
+    pub proximity_alert_radius: Option<u32>,
+    pub reply_markup: Option<ReplyMarkup>,
+    pub business_connection_id: Option<BusinessConnectionId>,
+}
+
+pub async fn edit_message_live_location(
+    body: web::Json<EditMessageLiveLocationBody>,
+    state: web::Data<Mutex<State>>,
+) -> RouteResult {
+    match (
+        body.chat_id.clone(),
+        body.message_id,
+        body.inline_message_id.clone(),
+    ) {
+        (Some(_), Some(message_id), None) => {
+            let mut lock = lock_state(&state)?;
+            let Some(old_message) = lock.messages.get_message(message_id) else {
+                return Err(RouteError::from_api_error(ApiError::MessageToEditNotFound));
+            };
+
+            // Only live locations that weren't stopped can be edited
+            let Some(old_location) = old_message
+                .location()
+                .filter(|location| location.live_period.is_some())
+                .copied()
+            else {
+                return Err(RouteError::from_api_error(ApiError::MessageCantBeEdited));
+            };
+
+            // The accuracy and the heading describe the new position, so they are replaced, but
+            // the live period and the proximity alerts are settings of the live location, and
+            // are kept if they aren't changed
+            let mut location = old_location;
+            location.latitude = body.latitude;
+            location.longitude = body.longitude;
+            location.horizontal_accuracy = body.horizontal_accuracy;
+            location.heading = body.heading;
+            location.live_period = body.live_period.or(old_location.live_period);
+            location.proximity_alert_radius = body
+                .proximity_alert_radius
+                .or(old_location.proximity_alert_radius);
+
+            let old_reply_markup = old_message
+                .reply_markup()
+                .map(|kb| ReplyMarkup::InlineKeyboard(kb.clone()));
+            if location == old_location && old_reply_markup == body.reply_markup {
+                return Err(RouteError::from_api_error(ApiError::MessageNotModified));
+            }
+
+            lock.messages
+                .edit_message_field(message_id, "location", location);
+
+            lock.set_edit_date(message_id);
+
+            let message = lock
+                .messages
+                .edit_message_reply_markup(message_id, body.reply_markup.clone())
+                .ok_or_else(|| RouteError::from_api_error(ApiError::MessageToEditNotFound))?;
+
+            lock.responses
+                .edited_messages_live_location
+                .push(EditedMessageLiveLocation {
+                    message: message.clone(),
+                    bot_request: body.into_inner(),
+                });
+
+            Ok(make_telegram_result(message))
+        }
+        // Inline messages aren't stored, so the edit is only recorded
+        (None, None, Some(inline_message_id)) => {
+            let mut lock = lock_state(&state)?;
+            lock.responses.inline_message_edits.push(InlineMessageEdit {
+                method: "EditMessageLiveLocation".to_owned(),
+                inline_message_id,
+                business_connection_id: body.business_connection_id.clone(),
+            });
+            Ok(make_telegram_result(true))
+        }
+        _ => Err(RouteError::bad_request(
+            "No message_id or inline_message_id were provided",
+        )),
+    }
+}
@@ -8,7 +8,7 @@ use super::BodyChatId;
 use crate::{
     server::{
         routes::{check_if_message_exists, make_telegram_result},
-        EditedMessageReplyMarkup,
+        EditedMessageReplyMarkup, InlineMessageEdit,
     },
     state::State,
 };
@@ -34,6 +34,7 @@ pub async fn edit_message_reply_markup(
         (Some(_), Some(message_id), None) => {
             let mut lock = state.lock().unwrap();
             check_if_message_exists!(lock, message_id);
+            lock.set_edit_date(message_id);
 
             let message = match body.reply_markup.clone() {
                 Some(reply_markup) => lock
@@ -55,7 +56,16 @@ pub async fn edit_message_reply_markup(
 
             make_telegram_result(message)
         }
-        (None, None, Some(_)) => make_telegram_result(true),
+        // Inline messages aren't stored, so the edit is only recorded
+        (None, None, Some(inline_message_id)) => {
+            let mut lock = state.lock().unwrap();
+            lock.responses.inline_message_edits.push(InlineMessageEdit {
+                method: "EditMessageReplyMarkup".to_owned(),
+                inline_message_id,
+                business_connection_id: body.business_connection_id.clone(),
+            });
+            make_telegram_result(true)
+        }
         _ => ErrorBadRequest("No message_id or inline_message_id were provided").into(),
     }
 }
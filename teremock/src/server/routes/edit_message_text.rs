@@ -9,9 +9,13 @@ use teloxide::{
 
 use super::{
     common::{lock_state, RouteError, RouteResult},
+    entities::{text_with_entities, validate_entities},
     make_telegram_result, BodyChatId,
 };
-use crate::{server::EditedMessageText, state::State};
+use crate::{
+    server::{EditedMessageText, InlineMessageEdit},
+    state::State,
+};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct EditMessageTextBody {
@@ -37,6 +41,10 @@ pub async fn edit_message_text(
     ) {
         (Some(_), Some(message_id), None) => {
             let mut lock = lock_state(&state)?;
+            let (text, entities) =
+                text_with_entities(Some(&body.text), body.parse_mode, body.entities.as_deref())?;
+            let text = text.unwrap_or_default();
+            validate_entities(&text, &entities)?;
             let Some(old_message) = lock.messages.get_message(message_id) else {
                 return Err(RouteError::from_api_error(ApiError::MessageToEditNotFound));
             };
@@ -44,17 +52,15 @@ pub async fn edit_message_text(
             let old_reply_markup = old_message
                 .reply_markup()
                 .map(|kb| ReplyMarkup::InlineKeyboard(kb.clone()));
-            if old_message.text() == Some(&body.text) && old_reply_markup == body.reply_markup {
+            if old_message.text() == Some(&text) && old_reply_markup == body.reply_markup {
                 return Err(RouteError::from_api_error(ApiError::MessageNotModified));
             }
 
+            lock.messages.edit_message_field(message_id, "text", text);
             lock.messages
-                .edit_message_field(message_id, "text", body.text.clone());
-            lock.messages.edit_message_field(
-                message_id,
-                "entities",
-                body.entities.clone().unwrap_or_default(),
-            );
+                .edit_message_field(message_id, "entities", entities);
+
+            lock.set_edit_date(message_id);
 
             let message = lock
                 .messages
@@ -68,8 +74,16 @@ pub async fn edit_message_text(
 
             Ok(make_telegram_result(message))
         }
-        // No implementation for inline messages yet, so just return success
-        (None, None, Some(_)) => Ok(make_telegram_result(true)),
+        // Inline messages aren't stored, so the edit is only recorded
+        (None, None, Some(inline_message_id)) => {
+            let mut lock = lock_state(&state)?;
+            lock.responses.inline_message_edits.push(InlineMessageEdit {
+                method: "EditMessageText".to_owned(),
+                inline_message_id,
+                business_connection_id: body.business_connection_id.clone(),
+            });
+            Ok(make_telegram_result(true))
+        }
         _ => Err(RouteError::bad_request(
             "No message_id or inline_message_id were provided",
         )),
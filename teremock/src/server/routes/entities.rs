@@ -0,0 +1,712 @@
+//! Parsing of `parse_mode` formatted text into plain text and message entities,
+//! imitating what Telegram does before storing a message.
+//!
+//! All offsets and lengths are counted in UTF-16 code units, just like Telegram does.
+use teloxide::{
+    types::{CustomEmojiId, MessageEntity, MessageEntityKind, ParseMode, ReplyParameters},
+    ApiError,
+};
+
+use super::common::RouteError;
+
+/// Returns the text and entities a message would end up with.
+///
+/// Explicitly passed entities always win (Telegram ignores `parse_mode` in that case),
+/// otherwise the text is parsed with `parse_mode`, if there is one.
+pub fn text_with_entities(
+    text: Option<&str>,
+    parse_mode: Option<ParseMode>,
+    entities: Option<&[MessageEntity]>,
+) -> Result<(Option<String>, Vec<MessageEntity>), RouteError> {
+    let Some(text) = text else {
+        return Ok((None, vec![]));
+    };
+    match (entities, parse_mode) {
+        (Some(entities), _) => Ok((Some(text.to_owned()), entities.to_vec())),
+        (None, Some(parse_mode)) => {
+            let (text, entities) = parse_entities(text, parse_mode).map_err(|error| {
+                RouteError::from_api_error(ApiError::CantParseEntities(format!(
+                    "Bad Request: can't parse entities: {error}"
+                )))
+            })?;
+            Ok((Some(text), entities))
+        }
+        (None, None) => Ok((Some(text.to_owned()), vec![])),
+    }
+}
+
+/// Returns the quote of the reply parameters and its entities, parsed with `quote_parse_mode`
+/// if there is one. Fails like Telegram does if the parse mode is unknown, or the quote can't be
+/// parsed with it.
+pub fn quote_with_entities(
+    reply_parameters: &ReplyParameters,
+) -> Result<Option<(String, Vec<MessageEntity>)>, RouteError> {
+    let parse_mode = reply_parameters
+        .quote_parse_mode
+        .as_deref()
+        .map(|parse_mode| {
+            parse_mode.parse().map_err(|_| {
+                RouteError::bad_request(&format!(
+                    "Bad Request: unsupported parse_mode {parse_mode:?}"
+                ))
+            })
+        })
+        .transpose()?;
+    let (quote, entities) = text_with_entities(
+        reply_parameters.quote.as_deref(),
+        parse_mode,
+        reply_parameters.quote_entities.as_deref(),
+    )?;
+    Ok(quote.map(|quote| (quote, entities)))
+}
+
+/// Checks that all of the entities fit into the text, the same way Telegram does,
+/// counting the text length in UTF-16 code units.
+///
+/// Entities may only overlap if one of them is nested in the other, and nothing can be nested
+/// in `code` or `pre`. If a `code` or `pre` entity has the same bounds as another entity, it is
+/// the inner one, no matter the order of the entities.
+pub fn validate_entities(text: &str, entities: &[MessageEntity]) -> Result<(), RouteError> {
+    let text_len = text.encode_utf16().count();
+    if let Some(entity) = entities.iter().find(|entity| {
+        entity
+            .offset
+            .checked_add(entity.length)
+            .is_none_or(|end| end > text_len)
+    }) {
+        return Err(RouteError::from_api_error(ApiError::CantParseEntities(
+            format!(
+                "Bad Request: can't parse entities: entity at offset {} with length {} is out of \
+                 the text bounds of {text_len} UTF-16 code units",
+                entity.offset, entity.length
+            ),
+        )));
+    }
+
+    for (i, first) in entities.iter().enumerate() {
+        for second in &entities[i + 1..] {
+            // All of the entities are in the text bounds, so this can't overflow
+            let (first_end, second_end) =
+                (first.offset + first.length, second.offset + second.length);
+            if first.offset >= second_end || second.offset >= first_end {
+                continue;
+            }
+            let same_bounds = first.offset == second.offset && first_end == second_end;
+            let (outer, inner) = if first.offset <= second.offset
+                && second_end <= first_end
+                && !(same_bounds && is_code(first))
+            {
+                (first, second)
+            } else if second.offset <= first.offset && first_end <= second_end {
+                (second, first)
+            } else {
+                return Err(overlap_error(first, second, "overlap"));
+            };
+            if is_code(outer) {
+                return Err(overlap_error(inner, outer, "can't be nested in"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_code(entity: &MessageEntity) -> bool {
+    matches!(
+        entity.kind,
+        MessageEntityKind::Code | MessageEntityKind::Pre { .. }
+    )
+}
+
+fn overlap_error(first: &MessageEntity, second: &MessageEntity, problem: &str) -> RouteError {
+    RouteError::from_api_error(ApiError::CantParseEntities(format!(
+        "Bad Request: can't parse entities: entity at offset {} with length {} {problem} the \
+         entity at offset {} with length {}",
+        first.offset, first.length, second.offset, second.length
+    )))
+}
+
+/// Parses `text` formatted with `parse_mode`, returning the text with all of the markup
+/// stripped, and the entities that the markup described.
+pub fn parse_entities(
+    text: &str,
+    parse_mode: ParseMode,
+) -> Result<(String, Vec<MessageEntity>), String> {
+    #[allow(deprecated)]
+    match parse_mode {
+        ParseMode::Html => parse_html(text),
+        ParseMode::MarkdownV2 => parse_markdown(text, true),
+        ParseMode::Markdown => parse_markdown(text, false),
+    }
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    utf16_len: usize,
+    entities: Vec<MessageEntity>,
+}
+
+impl Output {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.utf16_len += c.len_utf16();
+    }
+
+    fn push_str(&mut self, s: &str) {
+        s.chars().for_each(|c| self.push(c));
+    }
+
+    fn close(&mut self, kind: MessageEntityKind, offset: usize) {
+        let length = self.utf16_len - offset;
+        // Empty entities are just dropped by telegram
+        if length > 0 {
+            self.entities.push(MessageEntity {
+                kind,
+                offset,
+                length,
+            });
+        }
+    }
+
+    fn finish(mut self) -> (String, Vec<MessageEntity>) {
+        // Inner entities are closed first, but telegram sorts them by offset
+        self.entities
+            .sort_by(|a, b| a.offset.cmp(&b.offset).then(b.length.cmp(&a.length)));
+        (self.text, self.entities)
+    }
+}
+
+fn text_link(url: &str) -> Result<MessageEntityKind, String> {
+    if let Some(custom_emoji_id) = url.strip_prefix("tg://emoji?id=") {
+        return Ok(MessageEntityKind::CustomEmoji {
+            custom_emoji_id: CustomEmojiId(custom_emoji_id.to_owned()),
+        });
+    }
+    url::Url::parse(url)
+        .map(|url| MessageEntityKind::TextLink { url })
+        .map_err(|_| format!("Wrong URL '{url}'"))
+}
+
+//
+// HTML
+//
+
+struct OpenTag {
+    name: String,
+    offset: usize,
+    kind: Option<MessageEntityKind>,
+}
+
+fn parse_html(text: &str) -> Result<(String, Vec<MessageEntity>), String> {
+    let mut output = Output::default();
+    let mut open_tags: Vec<OpenTag> = vec![];
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let end = rest.find('>').ok_or_else(|| {
+                    format!("Unclosed start tag at byte offset {}", offset(text, rest))
+                })?;
+                let tag = &rest[1..end];
+                rest = &rest[end + 1..];
+
+                if let Some(name) = tag.strip_prefix('/') {
+                    let name = name.trim().to_lowercase();
+                    let open_tag = open_tags
+                        .pop()
+                        .ok_or_else(|| format!("Unexpected end tag '{name}'"))?;
+                    if open_tag.name != name {
+                        return Err(format!(
+                            "Unmatched end tag, expected '</{}>', found '</{name}>'",
+                            open_tag.name
+                        ));
+                    }
+                    if let Some(kind) = open_tag.kind {
+                        output.close(kind, open_tag.offset);
+                    }
+                    continue;
+                }
+
+                let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                let name = name.to_lowercase();
+                let kind = match name.as_str() {
+                    "b" | "strong" => Some(MessageEntityKind::Bold),
+                    "i" | "em" => Some(MessageEntityKind::Italic),
+                    "u" | "ins" => Some(MessageEntityKind::Underline),
+                    "s" | "strike" | "del" => Some(MessageEntityKind::Strikethrough),
+                    "tg-spoiler" => Some(MessageEntityKind::Spoiler),
+                    "span"
+                        if html_attribute(attributes, "class").as_deref() == Some("tg-spoiler") =>
+                    {
+                        Some(MessageEntityKind::Spoiler)
+                    }
+                    "pre" => Some(MessageEntityKind::Pre { language: None }),
+                    "code" => {
+                        let language = html_attribute(attributes, "class")
+                            .and_then(|class| class.strip_prefix("language-").map(str::to_owned));
+                        match open_tags.last_mut() {
+                            // <pre><code class="language-rust"> is a single pre entity
+                            Some(OpenTag {
+                                kind:
+                                    Some(MessageEntityKind::Pre {
+                                        language: pre_language,
+                                    }),
+                                offset,
+                                ..
+                            }) if *offset == output.utf16_len => {
+                                *pre_language = language;
+                                None
+                            }
+                            _ => Some(MessageEntityKind::Code),
+                        }
+                    }
+                    "a" => {
+                        let href = html_attribute(attributes, "href")
+                            .ok_or_else(|| "Tag 'a' must have attribute 'href'".to_string())?;
+                        Some(text_link(&href)?)
+                    }
+                    "tg-emoji" => {
+                        let emoji_id = html_attribute(attributes, "emoji-id").ok_or_else(|| {
+                            "Tag 'tg-emoji' must have attribute 'emoji-id'".to_string()
+                        })?;
+                        Some(MessageEntityKind::CustomEmoji {
+                            custom_emoji_id: CustomEmojiId(emoji_id),
+                        })
+                    }
+                    "blockquote" => {
+                        if attributes.split_whitespace().any(|a| a == "expandable") {
+                            Some(MessageEntityKind::ExpandableBlockquote)
+                        } else {
+                            Some(MessageEntityKind::Blockquote)
+                        }
+                    }
+                    _ => return Err(format!("Unsupported start tag '{name}'")),
+                };
+                open_tags.push(OpenTag {
+                    name,
+                    offset: output.utf16_len,
+                    kind,
+                });
+            }
+            '&' => {
+                let (decoded, consumed) = decode_html_entity(rest);
+                output.push_str(decoded.as_deref().unwrap_or("&"));
+                rest = &rest[consumed..];
+            }
+            _ => {
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if let Some(open_tag) = open_tags.last() {
+        return Err(format!(
+            "Can't find end tag corresponding to start tag '{}'",
+            open_tag.name
+        ));
+    }
+    Ok(output.finish())
+}
+
+fn offset(text: &str, rest: &str) -> usize {
+    text.len() - rest.len()
+}
+
+/// Finds the value of `key="value"` (or single-quoted, or unquoted) in tag attributes
+fn html_attribute(attributes: &str, key: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(index) = rest.find(key) {
+        let after = rest[index + key.len()..].trim_start();
+        let is_key_start = index == 0 || rest[..index].ends_with(char::is_whitespace);
+        if let (true, Some(value)) = (is_key_start, after.strip_prefix('=')) {
+            let value = value.trim_start();
+            let parsed = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+                _ => value.split(char::is_whitespace).next(),
+            };
+            return parsed.map(decode_html_entities);
+        }
+        rest = &rest[index + key.len()..];
+    }
+    None
+}
+
+fn decode_html_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' {
+            let (entity, consumed) = decode_html_entity(rest);
+            decoded.push_str(entity.as_deref().unwrap_or("&"));
+            rest = &rest[consumed..];
+        } else {
+            decoded.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    decoded
+}
+
+/// Decodes an html entity at the start of `text`, returning it and the amount of bytes consumed.
+/// If it is not a known entity, returns `None` and consumes only the `&`
+fn decode_html_entity(text: &str) -> (Option<String>, usize) {
+    let Some(end) = text.find(';').filter(|end| *end <= 10) else {
+        return (None, 1);
+    };
+    let name = &text[1..end];
+    let decoded = match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        _ => name
+            .strip_prefix("#x")
+            .or_else(|| name.strip_prefix("#X"))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .or_else(|| name.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+            .and_then(char::from_u32),
+    };
+    match decoded {
+        Some(c) => (Some(c.to_string()), end + 1),
+        None => (None, 1),
+    }
+}
+
+//
+// Markdown
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Marker {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Spoiler,
+    Link,
+}
+
+impl Marker {
+    fn kind(self) -> MessageEntityKind {
+        match self {
+            Marker::Bold => MessageEntityKind::Bold,
+            Marker::Italic => MessageEntityKind::Italic,
+            Marker::Underline => MessageEntityKind::Underline,
+            Marker::Strikethrough => MessageEntityKind::Strikethrough,
+            Marker::Spoiler => MessageEntityKind::Spoiler,
+            Marker::Link => unreachable!("Links are closed with their url"),
+        }
+    }
+}
+
+/// Parses both MarkdownV2 (`v2 == true`) and legacy Markdown
+fn parse_markdown(text: &str, v2: bool) -> Result<(String, Vec<MessageEntity>), String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = Output::default();
+    let mut open: Vec<(Marker, usize)> = vec![];
+    let mut i = 0;
+
+    let is_escapable = |c: char| {
+        if v2 {
+            c.is_ascii_punctuation()
+        } else {
+            matches!(c, '_' | '*' | '`' | '[')
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\\' if next.is_some_and(is_escapable) => {
+                output.push(next.unwrap());
+                i += 2;
+            }
+            '`' => {
+                let is_pre = next == Some('`') && chars.get(i + 2) == Some(&'`');
+                let delimiter = if is_pre { 3 } else { 1 };
+                i += delimiter;
+
+                let mut language = None;
+                if is_pre {
+                    let line_end = chars[i..].iter().position(|c| *c == '\n');
+                    let closing = find_code_end(&chars, i, true);
+                    if let (Some(line_end), Some(closing)) = (line_end, closing) {
+                        if i + line_end < closing {
+                            let first_line: String = chars[i..i + line_end].iter().collect();
+                            if !first_line.trim().is_empty() {
+                                language = Some(first_line.trim().to_owned());
+                            }
+                            i += line_end + 1;
+                        }
+                    }
+                }
+
+                let end = find_code_end(&chars, i, is_pre).ok_or_else(|| {
+                    format!(
+                        "Can't find end of {} entity",
+                        if is_pre { "Pre" } else { "Code" }
+                    )
+                })?;
+                let offset = output.utf16_len;
+                while i < end {
+                    if chars[i] == '\\' && matches!(chars.get(i + 1), Some('`' | '\\')) {
+                        i += 1;
+                    }
+                    output.push(chars[i]);
+                    i += 1;
+                }
+                i += delimiter;
+                if is_pre {
+                    output.close(MessageEntityKind::Pre { language }, offset);
+                } else {
+                    output.close(MessageEntityKind::Code, offset);
+                }
+            }
+            '*' => {
+                toggle(&mut output, &mut open, Marker::Bold, v2);
+                i += 1;
+            }
+            '_' if v2 && next == Some('_') => {
+                toggle(&mut output, &mut open, Marker::Underline, v2);
+                i += 2;
+            }
+            '_' => {
+                toggle(&mut output, &mut open, Marker::Italic, v2);
+                i += 1;
+            }
+            '~' if v2 => {
+                toggle(&mut output, &mut open, Marker::Strikethrough, v2);
+                i += 1;
+            }
+            '|' if v2 && next == Some('|') => {
+                toggle(&mut output, &mut open, Marker::Spoiler, v2);
+                i += 2;
+            }
+            '!' if v2 && next == Some('[') => {
+                // Custom emoji, the url decides what it is
+                open.push((Marker::Link, output.utf16_len));
+                i += 2;
+            }
+            '[' => {
+                open.push((Marker::Link, output.utf16_len));
+                i += 1;
+            }
+            ']' if open
+                .last()
+                .is_some_and(|(marker, _)| *marker == Marker::Link) =>
+            {
+                if next != Some('(') {
+                    return Err("Can't find end of a URL".to_string());
+                }
+                let url_end = chars[i + 2..]
+                    .iter()
+                    .position(|c| *c == ')')
+                    .ok_or_else(|| "Can't find end of a URL".to_string())?;
+                let url: String = chars[i + 2..i + 2 + url_end]
+                    .iter()
+                    .filter(|c| **c != '\\')
+                    .collect();
+                let (_, offset) = open.pop().unwrap();
+                output.close(text_link(&url)?, offset);
+                i += url_end + 3;
+            }
+            _ => {
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if let Some((marker, _)) = open.last() {
+        return Err(format!("Can't find end of {marker:?} entity"));
+    }
+    Ok(output.finish())
+}
+
+fn find_code_end(chars: &[char], start: usize, is_pre: bool) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '`' if !is_pre => return Some(i),
+            '`' if chars.get(i + 1) == Some(&'`') && chars.get(i + 2) == Some(&'`') => {
+                return Some(i)
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn toggle(output: &mut Output, open: &mut Vec<(Marker, usize)>, marker: Marker, v2: bool) {
+    match open
+        .iter()
+        .rposition(|(open_marker, _)| *open_marker == marker)
+    {
+        Some(index) => {
+            let (marker, offset) = open.remove(index);
+            output.close(marker.kind(), offset);
+        }
+        // Legacy markdown doesn't support nested entities
+        None if !v2 && open.iter().any(|(marker, _)| *marker != Marker::Link) => {
+            let c = match marker {
+                Marker::Bold => '*',
+                _ => '_',
+            };
+            output.push(c);
+        }
+        None => open.push((marker, output.utf16_len)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_html() {
+        let (text, entities) = parse_entities(
+            "<b>bold <i>both</i></b> &lt;3 <a href=\"https://example.com\">link</a>",
+            ParseMode::Html,
+        )
+        .unwrap();
+
+        assert_eq!(text, "bold both <3 link");
+        assert_eq!(
+            entities,
+            vec![
+                MessageEntity::bold(0, 9),
+                MessageEntity::italic(5, 4),
+                MessageEntity::text_link(url::Url::parse("https://example.com").unwrap(), 13, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_html_pre_with_language() {
+        let (text, entities) = parse_entities(
+            "<pre><code class=\"language-rust\">fn main() {}</code></pre>",
+            ParseMode::Html,
+        )
+        .unwrap();
+
+        assert_eq!(text, "fn main() {}");
+        assert_eq!(
+            entities,
+            vec![MessageEntity::pre(Some("rust".to_owned()), 0, 12)]
+        );
+    }
+
+    #[test]
+    fn test_parse_html_errors() {
+        assert!(parse_entities("<b>unclosed", ParseMode::Html).is_err());
+        assert!(parse_entities("<b>wrong</i>", ParseMode::Html).is_err());
+        assert!(parse_entities("<marquee>nope</marquee>", ParseMode::Html).is_err());
+    }
+
+    #[test]
+    fn test_parse_markdown_v2() {
+        let (text, entities) = parse_entities(
+            "*bold _italic_* __under__ ~strike~ ||spoiler|| `code` [link](https://example.com) 1\\.5",
+            ParseMode::MarkdownV2,
+        )
+        .unwrap();
+
+        assert_eq!(text, "bold italic under strike spoiler code link 1.5");
+        assert_eq!(
+            entities,
+            vec![
+                MessageEntity::bold(0, 11),
+                MessageEntity::italic(5, 6),
+                MessageEntity::underline(12, 5),
+                MessageEntity::strikethrough(18, 6),
+                MessageEntity::spoiler(25, 7),
+                MessageEntity::code(33, 4),
+                MessageEntity::text_link(url::Url::parse("https://example.com").unwrap(), 38, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_markdown_v2_pre() {
+        let (text, entities) =
+            parse_entities("```rust\nlet a = 1;```", ParseMode::MarkdownV2).unwrap();
+
+        assert_eq!(text, "let a = 1;");
+        assert_eq!(
+            entities,
+            vec![MessageEntity::pre(Some("rust".to_owned()), 0, 10)]
+        );
+        assert!(parse_entities("*unclosed", ParseMode::MarkdownV2).is_err());
+    }
+
+    #[test]
+    fn test_validate_entities() {
+        // The emoji is two UTF-16 code units long, so the text is 7 units long
+        assert!(validate_entities("😀 bold", &[MessageEntity::bold(3, 4)]).is_ok());
+        assert!(validate_entities("😀 bold", &[MessageEntity::bold(3, 5)]).is_err());
+        assert!(validate_entities("text", &[]).is_ok());
+        assert!(validate_entities("text", &[MessageEntity::bold(usize::MAX, 1)]).is_err());
+    }
+
+    #[test]
+    fn test_validate_entities_overlap() {
+        let text = "bold and code";
+        // Nested entities are fine, even with the same bounds
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::bold(0, 13), MessageEntity::code(9, 4)]
+        )
+        .is_ok());
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::bold(0, 4), MessageEntity::italic(0, 4)]
+        )
+        .is_ok());
+        // With the same bounds, code is nested in the other entity, in either order
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::bold(0, 4), MessageEntity::code(0, 4)]
+        )
+        .is_ok());
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::code(0, 4), MessageEntity::bold(0, 4)]
+        )
+        .is_ok());
+        // Entities that only touch don't overlap
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::bold(0, 4), MessageEntity::code(4, 9)]
+        )
+        .is_ok());
+
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::bold(0, 10), MessageEntity::code(5, 8)]
+        )
+        .is_err());
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::code(0, 13), MessageEntity::bold(0, 4)]
+        )
+        .is_err());
+        assert!(validate_entities(
+            text,
+            &[MessageEntity::code(0, 4), MessageEntity::pre(None, 0, 4)]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        let (text, entities) = parse_entities("😀 <b>bold</b>", ParseMode::Html).unwrap();
+
+        assert_eq!(text, "😀 bold");
+        // The emoji is two UTF-16 code units long
+        assert_eq!(entities, vec![MessageEntity::bold(3, 4)]);
+    }
+}
@@ -2,11 +2,11 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{Me, MessageId, MessageKind, MessageOrigin};
+use teloxide::types::{MessageId, MessageKind, ThreadId};
 
 use super::{
     check_if_message_exists,
-    common::{lock_state, RouteError, RouteResult},
+    common::{lock_state, message_origin, RouteError, RouteResult},
     make_telegram_result, BodyChatId,
 };
 use crate::{server::ForwardedMessage, state::State};
@@ -23,7 +23,6 @@ pub struct ForwardMessageBody {
 
 pub async fn forward_message(
     body: web::Json<ForwardMessageBody>,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
@@ -35,43 +34,28 @@ pub async fn forward_message(
         return Err(RouteError::bad_request("Message has protected content"));
     }
 
-    let message_clone = message.clone();
+    let forward_origin = message_origin(&message);
     if let MessageKind::Common(ref mut common) = message.kind {
-        common.forward_origin = Some(if message.chat.is_channel() {
-            MessageOrigin::Channel {
-                date: message_clone.date,
-                chat: message_clone.chat,
-                message_id: message_clone.id,
-                author_signature: None,
-            }
-        } else if let Some(sender_chat) = &message.sender_chat {
-            MessageOrigin::Chat {
-                date: message_clone.date,
-                sender_chat: sender_chat.clone(),
-                author_signature: None,
-            }
-        } else if let Some(user) = &message.from {
-            MessageOrigin::User {
-                date: message_clone.date,
-                sender_user: user.clone(),
-            }
-        } else {
-            // This is probably unreachable.
-            MessageOrigin::HiddenUser {
-                date: message_clone.date,
-                sender_user_name: "Unknown user".to_string(),
-            }
-        });
+        common.forward_origin = Some(forward_origin);
         common.has_protected_content = body.protect_content.unwrap_or(false);
     }
 
     let last_id = lock.messages.max_message_id();
     message.id = MessageId(last_id + 1);
-    message.chat = body.chat_id.chat();
-    message.from = Some(me.user.clone());
-    let message = lock.messages.add_message(message);
+    if message.chat.is_channel() {
+        // Forwards of channel posts are shown as sent on behalf of the channel
+        message.sender_chat = Some(message.chat.clone());
+    }
+    message.chat = body.chat_id.chat(&lock);
+    message.thread_id = body
+        .message_thread_id
+        .map(|thread_id| ThreadId(MessageId(thread_id)));
+    message.is_topic_message = message.thread_id.is_some();
+    message.from = Some(lock.me.user.clone());
+    let message = lock.add_sent_message(message);
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.forwarded_messages.push(ForwardedMessage {
         message: message.clone(),
         bot_request: body.into_inner(),
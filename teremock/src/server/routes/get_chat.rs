@@ -0,0 +1,88 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+use teloxide::types::{
+    Chat, ChatFullInfo, ChatKind, ChatPublic, PublicChatKind, PublicChatSupergroup,
+};
+
+use super::{
+    common::{lock_state, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::{
+    dataset::{
+        MockChatFullInfoChannel, MockChatFullInfoGroup, MockChatFullInfoPrivate,
+        MockChatFullInfoSupergroup, MockSupergroupChat,
+    },
+    state::State,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GetChatBody {
+    pub chat_id: BodyChatId,
+}
+
+pub async fn get_chat(state: web::Data<Mutex<State>>, body: web::Json<GetChatBody>) -> RouteResult {
+    let lock = lock_state(&state)?;
+    let chat_id = body.chat_id.id();
+    // The registered chats are described as they are, the others the same as in
+    // `BodyChatId::chat`, negative ids are supergroups, positive are private chats
+    let chat = match lock.chats.get(&chat_id) {
+        Some(chat) => full_info(chat, &lock),
+        None if chat_id < 0 => full_info(&MockSupergroupChat::new().id(chat_id).build(), &lock),
+        None => MockChatFullInfoPrivate::new().id(chat_id).build(),
+    };
+    Ok(make_telegram_result(chat))
+}
+
+/// Describes a chat with everything the bot and the test set for it, like its permissions and
+/// the chat linked to it
+fn full_info(chat: &Chat, lock: &State) -> ChatFullInfo {
+    let chat_id = chat.id.0;
+    let title = chat.title().map(ToOwned::to_owned);
+    let username = chat.username().map(ToOwned::to_owned);
+    // Channels are linked to their discussion groups, and the groups back to the channels
+    let linked_chat_id = lock.linked_chats.get(&chat_id).copied().or_else(|| {
+        lock.linked_chats
+            .iter()
+            .find_map(|(&channel_id, &group_id)| (group_id == chat_id).then_some(channel_id))
+    });
+
+    if chat.is_channel() {
+        let mut full_info = MockChatFullInfoChannel::new().id(chat_id);
+        full_info.title = title;
+        full_info.username = username;
+        full_info.linked_chat_id = linked_chat_id;
+        full_info.build()
+    } else if chat.is_supergroup() {
+        let is_forum = matches!(
+            &chat.kind,
+            ChatKind::Public(ChatPublic {
+                kind: PublicChatKind::Supergroup(PublicChatSupergroup { is_forum: true, .. }),
+                ..
+            })
+        );
+        let mut full_info = MockChatFullInfoSupergroup::new()
+            .id(chat_id)
+            .is_forum(is_forum);
+        full_info.title = title;
+        full_info.username = username;
+        full_info.linked_chat_id = linked_chat_id;
+        full_info.permissions = lock.chat_permissions.get(&chat_id).cloned();
+        full_info.sticker_set_name = lock.chat_sticker_sets.get(&chat_id).cloned();
+        full_info.slow_mode_delay = lock.slow_mode_delays.get(&chat_id).copied();
+        full_info.build()
+    } else if chat.is_group() {
+        let mut full_info = MockChatFullInfoGroup::new().id(chat_id);
+        full_info.title = title;
+        full_info.permissions = lock.chat_permissions.get(&chat_id).cloned();
+        full_info.build()
+    } else {
+        let mut full_info = MockChatFullInfoPrivate::new().id(chat_id);
+        full_info.username = username;
+        full_info.first_name = chat.first_name().map(ToOwned::to_owned);
+        full_info.last_name = chat.last_name().map(ToOwned::to_owned);
+        full_info.build()
+    }
+}
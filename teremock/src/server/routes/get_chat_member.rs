@@ -0,0 +1,45 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+use teloxide::types::{ChatMember, ChatMemberKind, Member, UserId};
+
+use super::{
+    common::{lock_state, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::{dataset::MockUser, state::State};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GetChatMemberBody {
+    pub chat_id: BodyChatId,
+    pub user_id: u64,
+}
+
+pub async fn get_chat_member(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetChatMemberBody>,
+) -> RouteResult {
+    let lock = lock_state(&state)?;
+    let chat_id = body.chat_id.id();
+    // Users the server has seen keep their names, everyone else gets the default ones
+    let user = lock
+        .messages
+        .messages
+        .iter()
+        .rev()
+        .find_map(|message| {
+            message
+                .from
+                .clone()
+                .filter(|from| from.id.0 == body.user_id)
+        })
+        .unwrap_or_else(|| MockUser::new().id(UserId(body.user_id)).build());
+    let kind = lock
+        .chat_members
+        .get(&(chat_id, body.user_id))
+        .cloned()
+        .unwrap_or(ChatMemberKind::Member(Member { until_date: None }));
+
+    Ok(make_telegram_result(ChatMember { user, kind }))
+}
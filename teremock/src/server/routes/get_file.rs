@@ -8,6 +8,9 @@ use super::common::{lock_state, RouteError, RouteResult};
 use super::make_telegram_result;
 use crate::state::State;
 
+/// Bots can't download files bigger than 20 MB
+const MAX_FILE_SIZE: u32 = 20 * 1024 * 1024;
+
 #[derive(Deserialize)]
 pub struct GetFileQuery {
     file_id: FileId,
@@ -21,5 +24,8 @@ pub async fn get_file(
     let Some(file) = lock.files.iter().find(|f| f.id == query.file_id) else {
         return Err(RouteError::bad_request("File not found"));
     };
+    if file.size > MAX_FILE_SIZE {
+        return Err(RouteError::bad_request("Bad Request: file is too big"));
+    }
     Ok(make_telegram_result(file))
 }
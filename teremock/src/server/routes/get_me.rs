@@ -1,8 +1,14 @@
-use actix_web::{web, Responder};
-use teloxide::types::Me;
+use std::sync::Mutex;
 
-use super::make_telegram_result;
+use actix_web::web;
 
-pub async fn get_me(me: web::Data<Me>) -> impl Responder {
-    make_telegram_result(me)
+use super::{
+    common::{lock_state, RouteResult},
+    make_telegram_result,
+};
+use crate::state::State;
+
+pub async fn get_me(state: web::Data<Mutex<State>>) -> RouteResult {
+    let lock = lock_state(&state)?;
+    Ok(make_telegram_result(&lock.me))
 }
@@ -3,26 +3,34 @@ use std::{collections::HashMap, str::from_utf8};
 use actix_web::{error::ResponseError, http::header::ContentType, HttpResponse};
 use futures_util::{stream::StreamExt as _, TryStreamExt};
 use rand::distr::{Alphanumeric, SampleString};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use teloxide::{
     types::{Chat, MessageEntity, ParseMode, Seconds},
     ApiError,
 };
 
-use crate::dataset::{MockPrivateChat, MockSupergroupChat};
+use crate::{
+    dataset::{MockChannelChat, MockPrivateChat, MockSupergroupChat},
+    state::State,
+};
 
 pub mod answer_callback_query;
 pub mod ban_chat_member;
 pub mod common;
 pub mod copy_message;
+pub mod delete_chat_sticker_set;
 pub mod delete_message;
 pub mod delete_messages;
 pub mod download_file;
 pub mod edit_message_caption;
+pub mod edit_message_live_location;
 pub mod edit_message_reply_markup;
 pub mod edit_message_text;
+pub mod entities;
 pub mod forward_message;
+pub mod get_chat;
+pub mod get_chat_member;
 pub mod get_file;
 pub mod get_me;
 pub mod get_updates;
@@ -39,6 +47,7 @@ pub mod send_invoice;
 pub mod send_location;
 pub mod send_media_group;
 pub mod send_message;
+pub mod send_paid_media;
 pub mod send_photo;
 pub mod send_poll;
 pub mod send_sticker;
@@ -46,8 +55,11 @@ pub mod send_venue;
 pub mod send_video;
 pub mod send_video_note;
 pub mod send_voice;
+pub mod set_chat_permissions;
+pub mod set_chat_sticker_set;
 pub mod set_message_reaction;
 pub mod set_my_commands;
+pub mod stop_message_live_location;
 pub mod unban_chat_member;
 pub mod unpin_all_chat_messages;
 pub mod unpin_chat_message;
@@ -74,9 +86,32 @@ impl BodyChatId {
     }
 
     /// Returns the chat
-    pub fn chat(&self) -> Chat {
+    ///
+    /// A chat registered with `MockBot::register_chat` is returned as it was registered,
+    /// found by its id or username. Otherwise a text username like `@channel` becomes a channel
+    /// with that username, so the sent message can be linked to.
+    pub(crate) fn chat(&self, state: &State) -> Chat {
+        let registered = match self {
+            BodyChatId::Id(id) => state.chats.get(id),
+            BodyChatId::Text(username) => {
+                let username = username.trim_start_matches('@');
+                state
+                    .chats
+                    .values()
+                    .find(|chat| chat.username() == Some(username))
+            }
+        };
+        if let Some(chat) = registered {
+            return chat.clone();
+        }
+
         let chat_id: i64 = self.id();
-        if chat_id < 0 {
+        if let BodyChatId::Text(username) = self {
+            MockChannelChat::new()
+                .id(chat_id)
+                .username(username.trim_start_matches('@'))
+                .build()
+        } else if chat_id < 0 {
             MockSupergroupChat::new().id(chat_id).build()
         } else {
             MockPrivateChat::new().id(chat_id).build()
@@ -172,6 +207,20 @@ pub trait SerializeRawFields {
     ) -> Option<Self>
     where
         Self: Sized;
+
+    /// Deserializes the value of a raw multipart field, see [`raw_field`]
+    fn raw_field<T: DeserializeOwned>(raw: &str) -> Option<T> {
+        raw_field(raw)
+    }
+}
+
+/// Deserializes the value of a raw multipart field. Multipart requests have their strings,
+/// like a `parse_mode`, a username `chat_id` or a business connection id, without the JSON
+/// quotes, so a value that isn't valid JSON is read as a string.
+pub fn raw_field<T: DeserializeOwned>(raw: &str) -> Option<T> {
+    serde_json::from_str(raw)
+        .or_else(|_| serde_json::from_value(serde_json::Value::String(raw.to_owned())))
+        .ok()
 }
 
 #[derive(Debug, Serialize)]
@@ -327,3 +376,22 @@ where
         .to_string(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{send_voice::SendMessageVoiceBody, *};
+
+    #[test]
+    fn test_raw_field() {
+        assert_eq!(SendMessageVoiceBody::raw_field::<i64>("42"), Some(42));
+        assert_eq!(
+            SendMessageVoiceBody::raw_field::<ParseMode>("HTML"),
+            Some(ParseMode::Html)
+        );
+        assert!(matches!(
+            SendMessageVoiceBody::raw_field::<BodyChatId>("@channel"),
+            Some(BodyChatId::Text(username)) if username == "@channel"
+        ));
+        assert_eq!(SendMessageVoiceBody::raw_field::<bool>("maybe"), None);
+    }
+}
@@ -5,8 +5,7 @@ use actix_web::web;
 use mime::Mime;
 use serde::Deserialize;
 use teloxide::types::{
-    BusinessConnectionId, EffectId, Me, MessageEntity, ParseMode, ReplyMarkup, ReplyParameters,
-    Seconds,
+    BusinessConnectionId, EffectId, MessageEntity, ParseMode, ReplyMarkup, ReplyParameters, Seconds,
 };
 
 use super::{
@@ -14,6 +13,7 @@ use super::{
         generate_file_ids, lock_state, MessageSetup, RouteError, RouteResult,
         DEFAULT_AUDIO_MIME_TYPE,
     },
+    entities::text_with_entities,
     get_raw_multipart_fields, make_telegram_result, BodyChatId,
 };
 use crate::{
@@ -26,20 +26,18 @@ use crate::{
     MockMessageAudio,
 };
 
-pub async fn send_audio(
-    mut payload: Multipart,
-    me: web::Data<Me>,
-    state: web::Data<Mutex<State>>,
-) -> RouteResult {
+pub async fn send_audio(mut payload: Multipart, state: web::Data<Mutex<State>>) -> RouteResult {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = lock_state(&state)?;
 
     let body = SendMessageAudioBody::serialize_raw_fields(&fields, &attachments, FileType::Audio)
         .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -50,9 +48,15 @@ pub async fn send_audio(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
-    message.caption = body.caption.clone();
-    message.caption_entities = body.caption_entities.clone().unwrap_or_default();
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
+    (message.caption, message.caption_entities) = text_with_entities(
+        body.caption.as_deref(),
+        body.parse_mode,
+        body.caption_entities.as_deref(),
+    )?;
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
@@ -68,7 +72,7 @@ pub async fn send_audio(
     message.file_name = Some(body.file_name.clone());
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     if let Some(audio) = message.audio() {
         lock.files.push(teloxide::types::File {
@@ -78,6 +82,7 @@ pub async fn send_audio(
     }
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.sent_messages_audio.push(SentMessageAudio {
         message: message.clone(),
         bot_request: body,
@@ -2,7 +2,7 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{BusinessConnectionId, EffectId, Me, ReplyMarkup, ReplyParameters};
+use teloxide::types::{BusinessConnectionId, EffectId, ReplyMarkup, ReplyParameters};
 
 use super::{
     common::{lock_state, MessageSetup, RouteResult},
@@ -28,14 +28,15 @@ pub struct SendMessageContactBody {
 
 pub async fn send_contact(
     body: web::Json<SendMessageContactBody>,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -46,7 +47,10 @@ pub async fn send_contact(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
     message.phone_number = body.phone_number.clone();
     message.first_name = body.first_name.clone();
     message.last_name = body.last_name.clone();
@@ -55,9 +59,10 @@ pub async fn send_contact(
     message.business_connection_id = body.business_connection_id.clone();
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses
         .sent_messages_contact
         .push(SentMessageContact {
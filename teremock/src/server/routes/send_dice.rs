@@ -2,10 +2,10 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{BusinessConnectionId, DiceEmoji, ReplyMarkup, ReplyParameters};
+use teloxide::types::{BusinessConnectionId, DiceEmoji, EffectId, ReplyMarkup, ReplyParameters};
 
 use super::{
-    common::{lock_state, setup_reply_to_message, RouteResult},
+    common::{lock_state, MessageSetup, RouteResult},
     make_telegram_result, BodyChatId,
 };
 use crate::{server::SentMessageDice, state::State, MockMessageDice};
@@ -17,32 +17,44 @@ pub struct SendMessageDiceBody {
     pub emoji: Option<DiceEmoji>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
-    pub message_effect_id: Option<String>,
+    pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
     pub business_connection_id: Option<BusinessConnectionId>,
 }
 
 pub async fn send_dice(
-    state: web::Data<Mutex<State>>,
     body: web::Json<SendMessageDiceBody>,
+    state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
 
-    let chat = body.chat_id.chat();
-
-    // Validate reply_parameters if provided (returns error if message doesn't exist)
-    let _reply_to = setup_reply_to_message(&lock, body.reply_parameters.as_ref())?;
+    let chat = body.chat_id.chat(&lock);
+    let setup = MessageSetup::new(
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
+        body.protect_content,
+        body.reply_parameters.as_ref(),
+        body.reply_markup.as_ref(),
+        &lock,
+    )?;
 
     let mut message = MockMessageDice::new().chat(chat);
+    message.from = setup.from;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
+    // Dice messages have nowhere to put the reply in teloxide, so it is only checked by the setup
     message.emoji = body.emoji.unwrap_or(MockMessageDice::EMOJI);
-    // Random from 1 to 5 because it fits all the emoji
-    message.value = (1 + rand::random::<u8>() % 5) as u8;
+    message.value = rand::random_range(1..=max_value(&message.emoji));
+    // Dice messages have no `effect_id` in teloxide, so `Responses::effect_id` takes the effect
+    // from the request
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.sent_messages_dice.push(SentMessageDice {
         message: message.clone(),
         bot_request: body.into_inner(),
@@ -50,3 +62,12 @@ pub async fn send_dice(
 
     Ok(make_telegram_result(message))
 }
+
+/// The highest value Telegram can roll for the emoji, the lowest one is always 1
+fn max_value(emoji: &DiceEmoji) -> u8 {
+    match emoji {
+        DiceEmoji::Dice | DiceEmoji::Darts | DiceEmoji::Bowling => 6,
+        DiceEmoji::Basketball | DiceEmoji::Football => 5,
+        DiceEmoji::SlotMachine => 64,
+    }
+}
@@ -2,10 +2,10 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{LabeledPrice, Me, ReplyMarkup, ReplyParameters};
+use teloxide::types::{LabeledPrice, MessageId, ReplyMarkup, ReplyParameters, ThreadId};
 
 use super::{
-    common::{lock_state, RouteResult},
+    common::{lock_state, setup_reply, RouteResult},
     make_telegram_result, BodyChatId,
 };
 use crate::{server::SentMessageInvoice, state::State, MockMessageInvoice};
@@ -44,39 +44,39 @@ pub struct SendMessageInvoiceBody {
 
 pub async fn send_invoice(
     body: web::Json<SendMessageInvoiceBody>,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let mut message = MockMessageInvoice::new()
-        .chat(chat)
+        .chat(chat.clone())
         .title(body.title.clone())
         .description(body.description.clone())
         .start_parameter(body.start_parameter.clone().unwrap_or_default())
-        .total_amount(body.prices.first().unwrap().amount);
-    message.from = Some(me.user.clone());
+        .currency(body.currency.clone())
+        // Telegram shows the sum of all of the prices, in the smallest units of the currency
+        .total_amount(body.prices.iter().map(|price| price.amount).sum::<u32>());
+    message.from = Some(lock.me.user.clone());
+    message.thread_id = body
+        .message_thread_id
+        .map(|thread_id| ThreadId(MessageId(thread_id as i32)));
+    message.is_topic_message = message.thread_id.is_some();
 
     // Commented until teloxides new release
     // message.has_protected_content = body.protect_content.unwrap_or(false);
 
-    // if let Some(reply_parameters) = &body.reply_parameters {
-    //     check_if_message_exists!(lock, reply_parameters.message_id.0);
-    //     let reply_to_message = lock
-    //         .messages
-    //         .get_message(reply_parameters.message_id.0)
-    //         .unwrap();
-    //     message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    // }
+    // Invoice messages have nowhere to put the reply in teloxide, but it is still checked
+    setup_reply(&lock, &chat, body.reply_parameters.as_ref())?;
     // if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
     //     message.reply_markup = Some(markup);
     // }
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses
         .sent_messages_invoice
         .push(SentMessageInvoice {
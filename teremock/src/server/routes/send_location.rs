@@ -2,9 +2,7 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{
-    BusinessConnectionId, EffectId, LivePeriod, Me, ReplyMarkup, ReplyParameters,
-};
+use teloxide::types::{BusinessConnectionId, EffectId, LivePeriod, ReplyMarkup, ReplyParameters};
 
 use super::{
     common::{lock_state, MessageSetup, RouteResult},
@@ -32,14 +30,15 @@ pub struct SendMessageLocationBody {
 
 pub async fn send_location(
     body: web::Json<SendMessageLocationBody>,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -53,7 +52,10 @@ pub async fn send_location(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
     message.horizontal_accuracy = body.horizontal_accuracy;
     message.live_period = body.live_period;
     message.heading = body.heading;
@@ -62,9 +64,10 @@ pub async fn send_location(
     message.business_connection_id = body.business_connection_id.clone();
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses
         .sent_messages_location
         .push(SentMessageLocation {
@@ -3,18 +3,19 @@ use std::{collections::HashMap, sync::Mutex};
 use actix_multipart::Multipart;
 use actix_web::web;
 use rand::distr::{Alphanumeric, SampleString};
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
 use teloxide::types::{
-    BusinessConnectionId, EffectId, Me, MediaGroupId, Message, MessageEntity, MessageId, ParseMode,
-    ReplyParameters, Seconds,
+    BusinessConnectionId, EffectId, MediaGroupId, Message, MessageEntity, MessageId, ParseMode,
+    ReplyParameters, Seconds, ThreadId,
 };
 
 use super::{
-    common::{generate_file_ids, lock_state, setup_reply_to_message, RouteError, RouteResult},
-    get_raw_multipart_fields, make_telegram_result, Attachment, BodyChatId, MediaGroupInputMedia,
-    MediaGroupInputMediaAudio, MediaGroupInputMediaDocument, MediaGroupInputMediaPhoto,
-    MediaGroupInputMediaVideo,
+    common::{generate_file_ids, lock_state, setup_reply, RouteError, RouteResult},
+    entities::text_with_entities,
+    get_raw_multipart_fields, make_telegram_result, raw_field, Attachment, BodyChatId,
+    MediaGroupInputMedia, MediaGroupInputMediaAudio, MediaGroupInputMediaDocument,
+    MediaGroupInputMediaPhoto, MediaGroupInputMediaVideo,
 };
 use crate::{
     server::SentMediaGroup, state::State, MockMessageAudio, MockMessageDocument, MockMessagePhoto,
@@ -23,14 +24,13 @@ use crate::{
 
 pub async fn send_media_group(
     mut payload: Multipart,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = lock_state(&state)?;
 
-    let body = SendMediaGroupBody::serialize_raw_fields(&fields, &attachments)
-        .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
+    // An invalid item fails the whole group, before any of the messages are stored
+    let body = SendMediaGroupBody::serialize_raw_fields(&fields, &attachments)?;
 
     if body.media.len() > 10 {
         return Err(RouteError::bad_request("Too many media items"));
@@ -38,32 +38,42 @@ pub async fn send_media_group(
         return Err(RouteError::bad_request("Too few media items"));
     }
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let protect_content = body.protect_content;
     let message_effect_id = body.message_effect_id.clone();
     let business_connection_id = body.business_connection_id.clone();
 
     // All of messages in the media group are replying to the same message
-    let reply_to_message = setup_reply_to_message(&lock, body.reply_parameters.as_ref())?;
+    let (reply_to_message, external_reply) =
+        setup_reply(&lock, &chat, body.reply_parameters.as_ref())?;
 
+    let thread_id = body
+        .message_thread_id
+        .map(|thread_id| ThreadId(MessageId(thread_id as i32)));
     let media_group_id = MediaGroupId(Alphanumeric.sample_string(&mut rand::rng(), 16));
 
+    let captions = body
+        .media
+        .iter()
+        .map(caption_with_entities)
+        .collect::<Result<Vec<_>, _>>()?;
     let mut messages: Vec<Message> = vec![];
 
-    for media in &body.media {
+    for (media, (caption, caption_entities)) in body.media.iter().zip(captions) {
         let (file_id, file_unique_id) = generate_file_ids();
         let last_id = lock.messages.max_message_id();
-        let message: Message;
+        let mut message: Message;
         match media {
             MediaGroupInputMedia::InputMediaAudio(audio) => {
                 let mut mock_message = MockMessageAudio::new();
                 mock_message.chat = chat.clone();
-                mock_message.from = Some(me.user.clone());
+                mock_message.from = Some(lock.me.user.clone());
 
                 mock_message.has_protected_content = protect_content.unwrap_or(false);
                 mock_message.reply_to_message = reply_to_message.clone();
-                mock_message.caption = audio.caption.clone();
-                mock_message.caption_entities = audio.caption_entities.clone().unwrap_or_default();
+                mock_message.external_reply = external_reply.clone();
+                mock_message.caption = caption;
+                mock_message.caption_entities = caption_entities;
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.performer = audio.performer.clone();
                 mock_message.title = audio.title.clone();
@@ -88,13 +98,13 @@ pub async fn send_media_group(
             MediaGroupInputMedia::InputMediaDocument(document) => {
                 let mut mock_message = MockMessageDocument::new();
                 mock_message.chat = chat.clone();
-                mock_message.from = Some(me.user.clone());
+                mock_message.from = Some(lock.me.user.clone());
 
                 mock_message.has_protected_content = protect_content.unwrap_or(false);
                 mock_message.reply_to_message = reply_to_message.clone();
-                mock_message.caption = document.caption.clone();
-                mock_message.caption_entities =
-                    document.caption_entities.clone().unwrap_or_default();
+                mock_message.external_reply = external_reply.clone();
+                mock_message.caption = caption;
+                mock_message.caption_entities = caption_entities;
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.effect_id = message_effect_id.clone();
                 mock_message.business_connection_id = business_connection_id.clone();
@@ -116,12 +126,16 @@ pub async fn send_media_group(
             MediaGroupInputMedia::InputMediaPhoto(photo) => {
                 let mut mock_message = MockMessagePhoto::new();
                 mock_message.chat = chat.clone();
-                mock_message.from = Some(me.user.clone());
+                mock_message.from = Some(lock.me.user.clone());
 
                 mock_message.has_protected_content = protect_content.unwrap_or(false);
                 mock_message.reply_to_message = reply_to_message.clone();
-                mock_message.caption = photo.caption.clone();
-                mock_message.caption_entities = photo.caption_entities.clone().unwrap_or_default();
+                mock_message.external_reply = external_reply.clone();
+                mock_message.caption = caption;
+                mock_message.caption_entities = caption_entities;
+                mock_message.show_caption_above_media =
+                    photo.show_caption_above_media.unwrap_or(false);
+                mock_message.has_media_spoiler = photo.has_spoiler.unwrap_or_default();
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.effect_id = message_effect_id.clone();
                 mock_message.business_connection_id = business_connection_id.clone();
@@ -145,12 +159,16 @@ pub async fn send_media_group(
             MediaGroupInputMedia::InputMediaVideo(video) => {
                 let mut mock_message = MockMessageVideo::new();
                 mock_message.chat = chat.clone();
-                mock_message.from = Some(me.user.clone());
+                mock_message.from = Some(lock.me.user.clone());
 
                 mock_message.has_protected_content = protect_content.unwrap_or(false);
                 mock_message.reply_to_message = reply_to_message.clone();
-                mock_message.caption = video.caption.clone();
-                mock_message.caption_entities = video.caption_entities.clone().unwrap_or_default();
+                mock_message.external_reply = external_reply.clone();
+                mock_message.caption = caption;
+                mock_message.caption_entities = caption_entities;
+                mock_message.show_caption_above_media =
+                    video.show_caption_above_media.unwrap_or(false);
+                mock_message.has_media_spoiler = video.has_spoiler.unwrap_or_default();
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.effect_id = message_effect_id.clone();
                 mock_message.business_connection_id = business_connection_id.clone();
@@ -178,11 +196,15 @@ pub async fn send_media_group(
             }
         }
 
-        messages.push(message.clone());
-        lock.messages.add_message(message);
+        message.thread_id = thread_id;
+        message.is_topic_message = thread_id.is_some();
+        messages.push(lock.add_sent_message(message));
     }
 
     lock.responses.sent_messages.extend(messages.clone());
+    for message in &messages {
+        lock.auto_forward(message);
+    }
     lock.responses.sent_media_group.push(SentMediaGroup {
         messages: messages.clone(),
         bot_request: body,
@@ -207,63 +229,56 @@ impl SendMediaGroupBody {
     fn serialize_raw_fields(
         fields: &HashMap<String, String>,
         attachments: &HashMap<String, Attachment>,
-    ) -> Option<Self> {
-        let raw_media: Vec<Value> = serde_json::from_str(fields.get("media")?).ok()?;
+    ) -> Result<Self, RouteError> {
+        let raw_media: Vec<Value> = fields
+            .get("media")
+            .and_then(|raw_media| serde_json::from_str(raw_media).ok())
+            .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
         let mut media: Vec<MediaGroupInputMedia> = vec![];
-        for raw_media_item in raw_media.iter() {
-            let raw_media_string = raw_media_item.get("media").unwrap().as_str().unwrap();
+        for (index, raw_media_item) in raw_media.iter().enumerate() {
+            let invalid_item = |reason: &str| invalid_item(index, reason);
+
+            let raw_media_string = raw_media_item
+                .get("media")
+                .and_then(|media| media.as_str())
+                .ok_or_else(|| invalid_item("media is required"))?;
             let file_name;
             let file_data;
-            if raw_media_string.starts_with("attach://") {
-                let raw_name = raw_media_string.strip_prefix("attach://").unwrap();
+            if let Some(raw_name) = raw_media_string.strip_prefix("attach://") {
                 let attachment = attachments
                     .values()
                     .find(|a| a.raw_name == raw_name)
-                    .expect("No attachment was found!");
+                    .ok_or_else(|| invalid_item("the attached file was not found"))?;
                 file_name = Some(attachment.file_name.clone());
                 file_data = attachment.file_data.clone();
             } else {
                 file_name = None;
                 file_data = raw_media_item.get("media").unwrap().to_string();
             }
+            if raw_media_string.is_empty() || file_data.is_empty() {
+                return Err(invalid_item("the media is empty"));
+            }
 
-            let media_type = raw_media_item.get("type").unwrap();
-            let caption = raw_media_item
-                .get("caption")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let parse_mode: Option<ParseMode> = raw_media_item
-                .get("parse_mode")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let caption_entities: Option<Vec<MessageEntity>> = raw_media_item
-                .get("caption_entities")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let duration: Option<Seconds> = raw_media_item
-                .get("duration")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let performer = raw_media_item
-                .get("performer")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let title = raw_media_item
-                .get("title")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let disable_content_type_detection: Option<bool> = raw_media_item
-                .get("disable_content_type_detection")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let show_caption_above_media: Option<bool> = raw_media_item
-                .get("show_caption_above_media")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let has_spoiler: Option<bool> = raw_media_item
-                .get("has_spoiler")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let width: Option<u32> = raw_media_item
-                .get("width")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let height: Option<u32> = raw_media_item
-                .get("height")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
-            let supports_streaming: Option<bool> = raw_media_item
-                .get("supports_streaming")
-                .map(|s| serde_json::from_value(s.clone()).unwrap());
+            let media_type = raw_media_item
+                .get("type")
+                .and_then(|media_type| media_type.as_str())
+                .ok_or_else(|| invalid_item("type is required"))?;
+            let caption = item_field(raw_media_item, index, "caption")?;
+            let parse_mode: Option<ParseMode> = item_field(raw_media_item, index, "parse_mode")?;
+            let caption_entities: Option<Vec<MessageEntity>> =
+                item_field(raw_media_item, index, "caption_entities")?;
+            let duration: Option<Seconds> = item_field(raw_media_item, index, "duration")?;
+            let performer = item_field(raw_media_item, index, "performer")?;
+            let title = item_field(raw_media_item, index, "title")?;
+            let disable_content_type_detection: Option<bool> =
+                item_field(raw_media_item, index, "disable_content_type_detection")?;
+            let show_caption_above_media: Option<bool> =
+                item_field(raw_media_item, index, "show_caption_above_media")?;
+            let has_spoiler: Option<bool> = item_field(raw_media_item, index, "has_spoiler")?;
+            let width: Option<u32> = item_field(raw_media_item, index, "width")?;
+            let height: Option<u32> = item_field(raw_media_item, index, "height")?;
+            let supports_streaming: Option<bool> =
+                item_field(raw_media_item, index, "supports_streaming")?;
 
             if media_type == "audio" {
                 media.push(MediaGroupInputMedia::InputMediaAudio(
@@ -322,27 +337,83 @@ impl SendMediaGroupBody {
                     },
                 ));
             } else {
-                panic!("Unknown media type: {}", media_type);
+                return Err(invalid_item(&format!("unknown media type {media_type}")));
             }
         }
 
-        Some(Self {
-            chat_id: serde_json::from_str(&fields.get("chat_id").unwrap().clone()).unwrap(),
-            message_thread_id: fields.get("message_thread_id").map(|s| s.parse().unwrap()),
+        Ok(Self {
+            chat_id: fields
+                .get("chat_id")
+                .and_then(|s| raw_field(s))
+                .ok_or_else(|| RouteError::bad_request("Bad Request: chat_id is invalid"))?,
+            message_thread_id: top_level_field(fields, "message_thread_id")?,
             media,
-            disable_notification: fields
-                .get("disable_notification")
-                .map(|s| s.parse().unwrap()),
-            protect_content: fields.get("protect_content").map(|s| s.parse().unwrap()),
+            disable_notification: top_level_field(fields, "disable_notification")?,
+            protect_content: top_level_field(fields, "protect_content")?,
             message_effect_id: fields
                 .get("message_effect_id")
                 .map(|s| s.to_string().into()),
-            reply_parameters: fields
-                .get("reply_parameters")
-                .map(|s| serde_json::from_str(s).unwrap()),
-            business_connection_id: fields
-                .get("business_connection_id")
-                .map(|s| serde_json::from_str(s).unwrap()),
+            reply_parameters: top_level_field(fields, "reply_parameters")?,
+            business_connection_id: top_level_field(fields, "business_connection_id")?,
         })
     }
 }
+
+/// Returns the caption of a media group item and its entities, parsed with `parse_mode`
+fn caption_with_entities(
+    media: &MediaGroupInputMedia,
+) -> Result<(Option<String>, Vec<MessageEntity>), RouteError> {
+    let (caption, parse_mode, caption_entities) = match media {
+        MediaGroupInputMedia::InputMediaAudio(audio) => {
+            (&audio.caption, audio.parse_mode, &audio.caption_entities)
+        }
+        MediaGroupInputMedia::InputMediaDocument(document) => (
+            &document.caption,
+            document.parse_mode,
+            &document.caption_entities,
+        ),
+        MediaGroupInputMedia::InputMediaPhoto(photo) => {
+            (&photo.caption, photo.parse_mode, &photo.caption_entities)
+        }
+        MediaGroupInputMedia::InputMediaVideo(video) => {
+            (&video.caption, video.parse_mode, &video.caption_entities)
+        }
+    };
+    text_with_entities(caption.as_deref(), parse_mode, caption_entities.as_deref())
+}
+
+/// The error of an invalid media group item, which fails the whole group
+fn invalid_item(index: usize, reason: &str) -> RouteError {
+    RouteError::bad_request(&format!(
+        "Bad Request: failed to send message #{} of the media group: {reason}",
+        index + 1
+    ))
+}
+
+/// Deserializes an optional field of a media group item
+fn item_field<T: DeserializeOwned>(
+    item: &Value,
+    index: usize,
+    name: &str,
+) -> Result<Option<T>, RouteError> {
+    item.get(name)
+        .map(|value| {
+            serde_json::from_value(value.clone())
+                .map_err(|_| invalid_item(index, &format!("{name} is invalid")))
+        })
+        .transpose()
+}
+
+/// Deserializes an optional top level field of the media group request
+fn top_level_field<T: DeserializeOwned>(
+    fields: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>, RouteError> {
+    fields
+        .get(name)
+        .map(|raw| {
+            raw_field(raw)
+                .ok_or_else(|| RouteError::bad_request(&format!("Bad Request: {name} is invalid")))
+        })
+        .transpose()
+}
@@ -3,12 +3,13 @@ use std::sync::Mutex;
 use actix_web::web;
 use serde::Deserialize;
 use teloxide::types::{
-    BusinessConnectionId, EffectId, LinkPreviewOptions, Me, MessageEntity, ParseMode, ReplyMarkup,
+    BusinessConnectionId, EffectId, LinkPreviewOptions, MessageEntity, ParseMode, ReplyMarkup,
     ReplyParameters,
 };
 
 use super::{
     common::{lock_state, MessageSetup, RouteResult},
+    entities::{text_with_entities, validate_entities},
     make_telegram_result, BodyChatId,
 };
 use crate::{dataset::message_common::MockMessageText, server::SentMessageText, state::State};
@@ -23,6 +24,7 @@ pub struct SendMessageTextBody {
     pub link_preview_options: Option<LinkPreviewOptions>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
@@ -31,34 +33,43 @@ pub struct SendMessageTextBody {
 
 pub async fn send_message(
     body: web::Json<SendMessageTextBody>,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
     let body = body.into_inner();
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
+    let (text, entities) =
+        text_with_entities(Some(&body.text), body.parse_mode, body.entities.as_deref())?;
+    let text = text.unwrap_or_default();
+    validate_entities(&text, &entities)?;
 
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
         &lock,
     )?;
 
-    let mut message = MockMessageText::new().text(&body.text).chat(chat);
+    let mut message = MockMessageText::new().text(&text).chat(chat);
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
-    message.entities = body.entities.clone().unwrap_or_default();
+    message.entities = entities;
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.sent_messages_text.push(SentMessageText {
         message: message.clone(),
         bot_request: body,
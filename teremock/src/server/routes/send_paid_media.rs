@@ -0,0 +1,244 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use actix_multipart::Multipart;
+use actix_web::web;
+use serde::Deserialize;
+use serde_json::Value;
+use teloxide::types::{
+    BusinessConnectionId, MessageEntity, PaidMedia, PaidMediaPhoto, PaidMediaVideo, ParseMode,
+    ReplyMarkup, ReplyParameters, Seconds,
+};
+
+use super::{
+    common::{generate_file_ids, lock_state, MessageSetup, RouteError, RouteResult},
+    entities::text_with_entities,
+    get_raw_multipart_fields, make_telegram_result, raw_field, Attachment, BodyChatId,
+};
+use crate::{
+    server::SentMessagePaidMedia, state::State, MockMessagePaidMedia, MockPhotoSize, MockVideo,
+};
+
+/// The most Telegram Stars that can be asked for a single paid media message
+const MAX_STAR_COUNT: u32 = 10000;
+
+pub async fn send_paid_media(
+    mut payload: Multipart,
+    state: web::Data<Mutex<State>>,
+) -> RouteResult {
+    let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
+    let mut lock = lock_state(&state)?;
+
+    let body = SendPaidMediaBody::serialize_raw_fields(&fields, &attachments)
+        .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
+
+    if body.media.len() > 10 {
+        return Err(RouteError::bad_request("Too many media items"));
+    } else if body.media.is_empty() {
+        return Err(RouteError::bad_request("Too few media items"));
+    }
+    if !(1..=MAX_STAR_COUNT).contains(&body.star_count) {
+        return Err(RouteError::bad_request("Bad Request: STARS_AMOUNT_INVALID"));
+    }
+    // Teloxide has nowhere to put the caption of paid media, but its markup is still checked
+    text_with_entities(
+        body.caption.as_deref(),
+        body.parse_mode,
+        body.caption_entities.as_deref(),
+    )?;
+
+    let chat = body.chat_id.chat(&lock);
+    let setup = MessageSetup::new(
+        &lock.me.user,
+        &chat,
+        None,
+        body.protect_content,
+        body.reply_parameters.as_ref(),
+        body.reply_markup.as_ref(),
+        &lock,
+    )?;
+
+    let mut paid_media = vec![];
+    let mut files = vec![];
+    for media in &body.media {
+        let (file_id, file_unique_id) = generate_file_ids();
+        match media {
+            PaidMediaInput::Photo(photo) => {
+                let photo_size = MockPhotoSize::new()
+                    .file_id(file_id)
+                    .file_unique_id(file_unique_id)
+                    .file_size(photo.file_data.len() as u32)
+                    .build();
+                files.push(teloxide::types::File {
+                    meta: photo_size.file.clone(),
+                    path: photo.file_name.clone(),
+                });
+                paid_media.push(PaidMedia::Photo(PaidMediaPhoto { photo: photo_size }));
+            }
+            PaidMediaInput::Video(video) => {
+                let mut mock_video = MockVideo::new();
+                mock_video.mime_type = mime_guess::from_path(&video.file_name).first();
+                mock_video.width = video.width.unwrap_or(100);
+                mock_video.height = video.height.unwrap_or(100);
+                mock_video.duration = video.duration.unwrap_or(Seconds::from_seconds(1));
+                mock_video.file_id = file_id;
+                mock_video.file_unique_id = file_unique_id;
+                mock_video.file_size = video.file_data.len() as u32;
+                mock_video.file_name = Some(video.file_name.clone());
+                let mock_video = mock_video.build();
+
+                files.push(teloxide::types::File {
+                    meta: mock_video.file.clone(),
+                    path: video.file_name.clone(),
+                });
+                paid_media.push(PaidMedia::Video(Box::new(PaidMediaVideo {
+                    video: mock_video,
+                })));
+            }
+        }
+    }
+
+    let mut message = MockMessagePaidMedia::new().chat(chat);
+    message.from = setup.from;
+    message.has_protected_content = setup.has_protected_content;
+    message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
+    message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
+    message.business_connection_id = body.business_connection_id.clone();
+    message.star_count = body.star_count;
+    message.paid_media = paid_media;
+
+    let last_id = lock.messages.max_message_id();
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
+
+    lock.files.extend(files);
+    lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
+    lock.responses
+        .sent_messages_paid_media
+        .push(SentMessagePaidMedia {
+            message: message.clone(),
+            bot_request: body,
+        });
+
+    Ok(make_telegram_result(message))
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub enum PaidMediaInput {
+    Photo(PaidMediaInputPhoto),
+    Video(PaidMediaInputVideo),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PaidMediaInputPhoto {
+    pub file_name: String,
+    pub file_data: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PaidMediaInputVideo {
+    pub file_name: String,
+    pub file_data: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration: Option<Seconds>,
+    pub supports_streaming: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SendPaidMediaBody {
+    pub chat_id: BodyChatId,
+    pub star_count: u32,
+    pub media: Vec<PaidMediaInput>,
+    pub payload: Option<String>,
+    pub caption: Option<String>,
+    pub parse_mode: Option<ParseMode>,
+    pub caption_entities: Option<Vec<MessageEntity>>,
+    pub show_caption_above_media: Option<bool>,
+    pub disable_notification: Option<bool>,
+    pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
+    pub reply_parameters: Option<ReplyParameters>,
+    pub reply_markup: Option<ReplyMarkup>,
+    pub business_connection_id: Option<BusinessConnectionId>,
+}
+
+impl SendPaidMediaBody {
+    fn serialize_raw_fields(
+        fields: &HashMap<String, String>,
+        attachments: &HashMap<String, Attachment>,
+    ) -> Option<Self> {
+        let raw_media: Vec<Value> = serde_json::from_str(fields.get("media")?).ok()?;
+        let mut media = vec![];
+        for raw_media_item in raw_media.iter() {
+            let raw_media_string = raw_media_item.get("media")?.as_str()?;
+            let (file_name, file_data) = match raw_media_string.strip_prefix("attach://") {
+                Some(raw_name) => {
+                    let attachment = attachments.values().find(|a| a.raw_name == raw_name)?;
+                    (
+                        Some(attachment.file_name.clone()),
+                        attachment.file_data.clone(),
+                    )
+                }
+                None => (None, raw_media_string.to_string()),
+            };
+
+            match raw_media_item.get("type")?.as_str()? {
+                "photo" => media.push(PaidMediaInput::Photo(PaidMediaInputPhoto {
+                    file_name: file_name.unwrap_or("no_name.jpg".to_string()),
+                    file_data,
+                })),
+                "video" => media.push(PaidMediaInput::Video(PaidMediaInputVideo {
+                    file_name: file_name.unwrap_or("no_name.mp4".to_string()),
+                    file_data,
+                    width: raw_media_item
+                        .get("width")
+                        .and_then(|s| serde_json::from_value(s.clone()).ok()),
+                    height: raw_media_item
+                        .get("height")
+                        .and_then(|s| serde_json::from_value(s.clone()).ok()),
+                    duration: raw_media_item
+                        .get("duration")
+                        .and_then(|s| serde_json::from_value(s.clone()).ok()),
+                    supports_streaming: raw_media_item
+                        .get("supports_streaming")
+                        .and_then(|s| serde_json::from_value(s.clone()).ok()),
+                })),
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            chat_id: raw_field(fields.get("chat_id")?)?,
+            star_count: fields.get("star_count")?.parse().ok()?,
+            media,
+            payload: fields.get("payload").cloned(),
+            caption: fields.get("caption").cloned(),
+            parse_mode: fields.get("parse_mode").and_then(|s| raw_field(s)),
+            caption_entities: fields
+                .get("caption_entities")
+                .and_then(|s| serde_json::from_str(s).ok()),
+            show_caption_above_media: fields
+                .get("show_caption_above_media")
+                .and_then(|s| s.parse().ok()),
+            disable_notification: fields
+                .get("disable_notification")
+                .and_then(|s| s.parse().ok()),
+            protect_content: fields.get("protect_content").and_then(|s| s.parse().ok()),
+            allow_paid_broadcast: fields
+                .get("allow_paid_broadcast")
+                .and_then(|s| s.parse().ok()),
+            reply_parameters: fields
+                .get("reply_parameters")
+                .and_then(|s| serde_json::from_str(s).ok()),
+            reply_markup: fields
+                .get("reply_markup")
+                .and_then(|s| serde_json::from_str(s).ok()),
+            business_connection_id: fields
+                .get("business_connection_id")
+                .and_then(|s| raw_field(s)),
+        })
+    }
+}
@@ -4,12 +4,13 @@ use actix_multipart::Multipart;
 use actix_web::web;
 use serde::Deserialize;
 use teloxide::types::{
-    BusinessConnectionId, EffectId, LinkPreviewOptions, Me, MessageEntity, ParseMode, ReplyMarkup,
+    BusinessConnectionId, EffectId, LinkPreviewOptions, MessageEntity, ParseMode, ReplyMarkup,
     ReplyParameters,
 };
 
 use super::{
     common::{generate_file_ids, lock_state, MessageSetup, RouteError, RouteResult},
+    entities::text_with_entities,
     get_raw_multipart_fields, make_telegram_result, BodyChatId,
 };
 use crate::{
@@ -22,20 +23,18 @@ use crate::{
     state::State,
 };
 
-pub async fn send_photo(
-    mut payload: Multipart,
-    me: web::Data<Me>,
-    state: web::Data<Mutex<State>>,
-) -> RouteResult {
+pub async fn send_photo(mut payload: Multipart, state: web::Data<Mutex<State>>) -> RouteResult {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = lock_state(&state)?;
 
     let body = SendMessagePhotoBody::serialize_raw_fields(&fields, &attachments, FileType::Photo)
         .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -46,10 +45,17 @@ pub async fn send_photo(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
-    message.caption = body.caption.clone();
-    message.caption_entities = body.caption_entities.clone().unwrap_or_default();
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
+    (message.caption, message.caption_entities) = text_with_entities(
+        body.caption.as_deref(),
+        body.parse_mode,
+        body.caption_entities.as_deref(),
+    )?;
     message.show_caption_above_media = body.show_caption_above_media.unwrap_or(false);
+    message.has_media_spoiler = body.has_spoiler.unwrap_or_default();
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
@@ -62,7 +68,7 @@ pub async fn send_photo(
         .build()];
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     if let Some(photo) = message.photo() {
         if let Some(first_photo) = photo.first() {
@@ -74,6 +80,7 @@ pub async fn send_photo(
     }
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.sent_messages_photo.push(SentMessagePhoto {
         message: message.clone(),
         bot_request: body,
@@ -95,6 +102,7 @@ pub struct SendMessagePhotoBody {
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
     pub show_caption_above_media: Option<bool>,
+    pub has_spoiler: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
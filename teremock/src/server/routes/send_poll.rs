@@ -3,17 +3,28 @@ use std::sync::Mutex;
 use actix_web::web;
 use chrono::DateTime;
 use serde::Deserialize;
-use teloxide::types::{
-    BusinessConnectionId, EffectId, InputPollOption, Me, MessageEntity, ParseMode, PollOption,
-    PollType, ReplyMarkup, ReplyParameters, Seconds,
+use teloxide::{
+    types::{
+        BusinessConnectionId, EffectId, InputPollOption, InputPollOptionFormatting, MessageEntity,
+        ParseMode, PollId, PollOption, PollType, ReplyMarkup, ReplyParameters, Seconds,
+    },
+    ApiError,
 };
 
 use super::{
-    common::{lock_state, MessageSetup, RouteResult},
+    common::{lock_state, MessageSetup, RouteError, RouteResult},
+    entities::{text_with_entities, validate_entities},
     make_telegram_result, BodyChatId,
 };
 use crate::{server::SentMessagePoll, state::State, MockMessagePoll};
 
+/// The longest poll question Telegram accepts, in characters
+const MAX_QUESTION_LENGTH: usize = 300;
+/// The longest poll option Telegram accepts, in characters
+const MAX_OPTION_LENGTH: usize = 100;
+const MIN_OPTION_COUNT: usize = 2;
+const MAX_OPTION_COUNT: usize = 12;
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct SendMessagePollBody {
     pub chat_id: BodyChatId,
@@ -43,13 +54,29 @@ pub struct SendMessagePollBody {
 pub async fn send_poll(
     state: web::Data<Mutex<State>>,
     body: web::Json<SendMessagePollBody>,
-    me: web::Data<Me>,
 ) -> RouteResult {
+    // The limits are for the question and the options as they are shown, without the
+    // formatting markup
+    let (question, question_entities) = text_with_entities(
+        Some(&body.question),
+        body.question_parse_mode,
+        body.question_entities.as_deref(),
+    )?;
+    let question = question.unwrap_or_default();
+    let options: Vec<PollOption> = body
+        .options
+        .iter()
+        .map(poll_option)
+        .collect::<Result<_, _>>()?;
+    validate_poll(&question, &options)?;
+    validate_entities(&question, &question_entities)?;
     let mut lock = lock_state(&state)?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -60,35 +87,41 @@ pub async fn send_poll(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
     message.business_connection_id = body.business_connection_id.clone();
 
-    message.question = body.question.clone();
-    let options: Vec<PollOption> = body
-        .options
-        .iter()
-        .map(|option| PollOption {
-            text: option.text.clone(),
-            text_entities: None,
-            voter_count: 0,
-        })
-        .collect();
+    message.question = question;
     message.options = options;
+    message.is_closed = body.is_closed.unwrap_or(false);
     message.is_anonymous = body.is_anonymous.unwrap_or(false);
     message.poll_type = body.r#type.clone().unwrap_or(PollType::Regular);
     message.allows_multiple_answers = body.allows_multiple_answers.unwrap_or(false);
     message.correct_option_id = body.correct_option_id;
-    message.explanation = body.explanation.clone();
-    message.explanation_entities = body.explanation_entities.clone();
+    let (explanation, explanation_entities) = text_with_entities(
+        body.explanation.as_deref(),
+        body.explanation_parse_mode,
+        body.explanation_entities.as_deref(),
+    )?;
+    message.explanation = explanation;
+    message.explanation_entities =
+        (!explanation_entities.is_empty()).then_some(explanation_entities);
     message.open_period = body.open_period;
     message.close_date = DateTime::from_timestamp(body.close_date.unwrap_or(0) as i64, 0);
     message.effect_id = body.message_effect_id.clone();
-    message.question_entities = body.question_entities.clone();
+    message.question_entities = (!question_entities.is_empty()).then_some(question_entities);
+    if let Some(poll_id) = lock.next_poll_id {
+        message.poll_id = PollId(poll_id.to_string());
+        lock.next_poll_id = Some(poll_id + 1);
+    }
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.sent_messages_poll.push(SentMessagePoll {
         message: message.clone(),
         bot_request: body.into_inner(),
@@ -96,3 +129,57 @@ pub async fn send_poll(
 
     Ok(make_telegram_result(message))
 }
+
+/// Turns the option the bot sent into the option of the poll, parsing its text if needed
+fn poll_option(option: &InputPollOption) -> Result<PollOption, RouteError> {
+    let (parse_mode, entities) = match &option.formatting {
+        Some(InputPollOptionFormatting::TextParseMode(parse_mode)) => (Some(*parse_mode), None),
+        Some(InputPollOptionFormatting::TextEntities(entities)) => {
+            (None, Some(entities.as_slice()))
+        }
+        None => (None, None),
+    };
+    let (text, text_entities) = text_with_entities(Some(&option.text), parse_mode, entities)?;
+    Ok(PollOption {
+        text: text.unwrap_or_default(),
+        text_entities: (!text_entities.is_empty()).then_some(text_entities),
+        voter_count: 0,
+    })
+}
+
+/// Checks the poll against the limits Telegram has for questions and options
+fn validate_poll(question: &str, options: &[PollOption]) -> Result<(), RouteError> {
+    if question.trim().is_empty() {
+        return Err(RouteError::from_api_error(
+            ApiError::PollQuestionMustBeNonEmpty,
+        ));
+    }
+    if question.chars().count() > MAX_QUESTION_LENGTH {
+        return Err(RouteError::from_api_error(
+            ApiError::PollQuestionLengthTooLong,
+        ));
+    }
+    if options.len() < MIN_OPTION_COUNT {
+        return Err(RouteError::from_api_error(
+            ApiError::PollMustHaveMoreOptions,
+        ));
+    }
+    if options.len() > MAX_OPTION_COUNT {
+        return Err(RouteError::from_api_error(
+            ApiError::PollCantHaveMoreOptions,
+        ));
+    }
+    for option in options {
+        if option.text.trim().is_empty() {
+            return Err(RouteError::from_api_error(
+                ApiError::PollOptionsMustBeNonEmpty,
+            ));
+        }
+        if option.text.chars().count() > MAX_OPTION_LENGTH {
+            return Err(RouteError::from_api_error(
+                ApiError::PollOptionsLengthTooLong,
+            ));
+        }
+    }
+    Ok(())
+}
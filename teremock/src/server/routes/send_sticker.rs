@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::Mutex};
 use actix_multipart::Multipart;
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{BusinessConnectionId, EffectId, Me, ReplyMarkup, ReplyParameters};
+use teloxide::types::{BusinessConnectionId, EffectId, ReplyMarkup, ReplyParameters};
 
 use super::{
     common::{lock_state, MessageSetup, RouteError, RouteResult},
@@ -19,11 +19,7 @@ use crate::{
     MockMessageSticker,
 };
 
-pub async fn send_sticker(
-    mut payload: Multipart,
-    me: web::Data<Me>,
-    state: web::Data<Mutex<State>>,
-) -> RouteResult {
+pub async fn send_sticker(mut payload: Multipart, state: web::Data<Mutex<State>>) -> RouteResult {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = lock_state(&state)?;
 
@@ -31,9 +27,11 @@ pub async fn send_sticker(
         SendMessageStickerBody::serialize_raw_fields(&fields, &attachments, FileType::Sticker)
             .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -44,19 +42,42 @@ pub async fn send_sticker(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
     message.emoji = body.emoji.clone();
+    // A sticker that is sent again by its file id keeps everything that the bot can't set, like
+    // its kind with the premium animation, or `needs_repainting`
+    if let Some(sticker) = lock.messages.messages.iter().rev().find_map(|message| {
+        message
+            .sticker()
+            .filter(|sticker| sticker.file.id.0 == body.file_data)
+    }) {
+        message.file_id = sticker.file.id.clone();
+        message.file_unique_id = sticker.file.unique_id.clone();
+        message.file_size = sticker.file.size;
+        message.width = sticker.width;
+        message.height = sticker.height;
+        message.kind = sticker.kind.clone();
+        message.flags = sticker.flags.clone();
+        message.thumbnail = sticker.thumbnail.clone();
+        message.set_name = sticker.set_name.clone();
+        message.needs_repainting = sticker.needs_repainting;
+        message.emoji = body.emoji.clone().or_else(|| sticker.emoji.clone());
+    }
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.files.push(teloxide::types::File {
         meta: message.sticker().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses
         .sent_messages_sticker
         .push(SentMessageSticker {
@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::Mutex};
 use actix_multipart::Multipart;
 use actix_web::web;
 use serde::Deserialize;
-use teloxide::types::{BusinessConnectionId, EffectId, Me, ReplyMarkup, ReplyParameters, Seconds};
+use teloxide::types::{BusinessConnectionId, EffectId, ReplyMarkup, ReplyParameters, Seconds};
 
 use super::{
     common::{generate_file_ids, lock_state, MessageSetup, RouteError, RouteResult},
@@ -21,7 +21,6 @@ use crate::{
 
 pub async fn send_video_note(
     mut payload: Multipart,
-    me: web::Data<Me>,
     state: web::Data<Mutex<State>>,
 ) -> RouteResult {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
@@ -31,9 +30,11 @@ pub async fn send_video_note(
         SendMessageVideoNoteBody::serialize_raw_fields(&fields, &attachments, FileType::Voice)
             .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -44,7 +45,10 @@ pub async fn send_video_note(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
 
     let (file_id, file_unique_id) = generate_file_ids();
 
@@ -57,13 +61,14 @@ pub async fn send_video_note(
     message.business_connection_id = body.business_connection_id.clone();
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.files.push(teloxide::types::File {
         meta: message.video_note().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses
         .sent_messages_video_note
         .push(SentMessageVideoNote {
@@ -5,12 +5,12 @@ use actix_web::web;
 use mime::Mime;
 use serde::Deserialize;
 use teloxide::types::{
-    BusinessConnectionId, EffectId, Me, MessageEntity, ParseMode, ReplyMarkup, ReplyParameters,
-    Seconds,
+    BusinessConnectionId, EffectId, MessageEntity, ParseMode, ReplyMarkup, ReplyParameters, Seconds,
 };
 
 use super::{
     common::{generate_file_ids, lock_state, MessageSetup, RouteError, RouteResult},
+    entities::text_with_entities,
     get_raw_multipart_fields, make_telegram_result, BodyChatId,
 };
 use crate::{
@@ -23,20 +23,18 @@ use crate::{
     MockMessageVoice,
 };
 
-pub async fn send_voice(
-    mut payload: Multipart,
-    me: web::Data<Me>,
-    state: web::Data<Mutex<State>>,
-) -> RouteResult {
+pub async fn send_voice(mut payload: Multipart, state: web::Data<Mutex<State>>) -> RouteResult {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = lock_state(&state)?;
 
     let body = SendMessageVoiceBody::serialize_raw_fields(&fields, &attachments, FileType::Voice)
         .ok_or_else(|| RouteError::bad_request("Failed to parse request body"))?;
 
-    let chat = body.chat_id.chat();
+    let chat = body.chat_id.chat(&lock);
     let setup = MessageSetup::new(
-        &me.user,
+        &lock.me.user,
+        &chat,
+        body.message_thread_id,
         body.protect_content,
         body.reply_parameters.as_ref(),
         body.reply_markup.as_ref(),
@@ -47,9 +45,15 @@ pub async fn send_voice(
     message.from = setup.from;
     message.has_protected_content = setup.has_protected_content;
     message.reply_to_message = setup.reply_to_message;
+    message.external_reply = setup.external_reply;
     message.reply_markup = setup.reply_markup;
-    message.caption = body.caption.clone();
-    message.caption_entities = body.caption_entities.clone().unwrap_or_default();
+    message.thread_id = setup.thread_id;
+    message.is_topic_message = setup.is_topic_message;
+    (message.caption, message.caption_entities) = text_with_entities(
+        body.caption.as_deref(),
+        body.parse_mode,
+        body.caption_entities.as_deref(),
+    )?;
     message.business_connection_id = body.business_connection_id.clone();
 
     let (file_id, file_unique_id) = generate_file_ids();
@@ -62,13 +66,14 @@ pub async fn send_voice(
     message.effect_id = body.message_effect_id.clone();
 
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build());
 
     lock.files.push(teloxide::types::File {
         meta: message.voice().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    lock.auto_forward(&message);
     lock.responses.sent_messages_voice.push(SentMessageVoice {
         message: message.clone(),
         bot_request: body,
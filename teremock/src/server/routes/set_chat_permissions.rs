@@ -0,0 +1,29 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+use teloxide::types::ChatPermissions;
+
+use super::{
+    common::{lock_state, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::state::State;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SetChatPermissionsBody {
+    pub chat_id: BodyChatId,
+    pub permissions: ChatPermissions,
+    pub use_independent_chat_permissions: Option<bool>,
+}
+
+pub async fn set_chat_permissions(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<SetChatPermissionsBody>,
+) -> RouteResult {
+    let mut lock = lock_state(&state)?;
+    lock.chat_permissions
+        .insert(body.chat_id.id(), body.permissions.clone());
+    lock.responses.set_chat_permissions.push(body.into_inner());
+    Ok(make_telegram_result(true))
+}
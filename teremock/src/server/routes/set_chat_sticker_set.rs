@@ -0,0 +1,27 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+
+use super::{
+    common::{lock_state, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::state::State;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SetChatStickerSetBody {
+    pub chat_id: BodyChatId,
+    pub sticker_set_name: String,
+}
+
+pub async fn set_chat_sticker_set(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<SetChatStickerSetBody>,
+) -> RouteResult {
+    let mut lock = lock_state(&state)?;
+    lock.chat_sticker_sets
+        .insert(body.chat_id.id(), body.sticker_set_name.clone());
+    lock.responses.set_chat_sticker_sets.push(body.into_inner());
+    Ok(make_telegram_result(true))
+}
@@ -0,0 +1,86 @@
+use std::sync::Mutex;
+
+use actix_web::web;
+use serde::Deserialize;
+use teloxide::{
+    types::{BusinessConnectionId, ReplyMarkup},
+    ApiError,
+};
+
+use super::{
+    common::{lock_state, RouteError, RouteResult},
+    make_telegram_result, BodyChatId,
+};
+use crate::{
+    server::{InlineMessageEdit, StoppedLiveLocation},
+    state::State,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StopMessageLiveLocationBody {
+    pub chat_id: Option<BodyChatId>,
+    pub message_id: Option<i32>,
+    pub inline_message_id: Option<String>,
+    pub reply_markup: Option<ReplyMarkup>,
+    pub business_connection_id: Option<BusinessConnectionId>,
+}
+
+pub async fn stop_message_live_location(
+    body: web::Json<StopMessageLiveLocationBody>,
+    state: web::Data<Mutex<State>>,
+) -> RouteResult {
+    match (
+        body.chat_id.clone(),
+        body.message_id,
+        body.inline_message_id.clone(),
+    ) {
+        (Some(_), Some(message_id), None) => {
+            let mut lock = lock_state(&state)?;
+            let Some(old_message) = lock.messages.get_message(message_id) else {
+                return Err(RouteError::from_api_error(ApiError::MessageToEditNotFound));
+            };
+
+            let Some(mut location) = old_message
+                .location()
+                .filter(|location| location.live_period.is_some())
+                .cloned()
+            else {
+                return Err(RouteError::from_api_error(ApiError::MessageCantBeEdited));
+            };
+
+            // Without a live period the location can't be edited anymore
+            location.live_period = None;
+            lock.messages
+                .edit_message_field(message_id, "location", location);
+
+            lock.set_edit_date(message_id);
+
+            let message = lock
+                .messages
+                .edit_message_reply_markup(message_id, body.reply_markup.clone())
+                .ok_or_else(|| RouteError::from_api_error(ApiError::MessageToEditNotFound))?;
+
+            lock.responses
+                .stopped_live_locations
+                .push(StoppedLiveLocation {
+                    message: message.clone(),
+                    bot_request: body.into_inner(),
+                });
+
+            Ok(make_telegram_result(message))
+        }
+        // Inline messages aren't stored, so the edit is only recorded
+        (None, None, Some(inline_message_id)) => {
+            let mut lock = lock_state(&state)?;
+            lock.responses.inline_message_edits.push(InlineMessageEdit {
+                method: "StopMessageLiveLocation".to_owned(),
+                inline_message_id,
+                business_connection_id: body.business_connection_id.clone(),
+            });
+            Ok(make_telegram_result(true))
+        }
+        _ => Err(RouteError::bad_request(
+            "No message_id or inline_message_id were provided",
+        )),
+    }
+}
@@ -2,6 +2,7 @@ use std::sync::Mutex;
 
 use actix_web::web;
 use serde::Deserialize;
+use teloxide::types::ChatMemberKind;
 
 use super::{
     common::{lock_state, RouteResult},
@@ -21,6 +22,16 @@ pub async fn unban_chat_member(
     body: web::Json<UnbanChatMemberBody>,
 ) -> RouteResult {
     let mut lock = lock_state(&state)?;
+    let member = (body.chat_id.id(), body.user_id);
+    let is_banned = matches!(
+        lock.chat_members.get(&member),
+        Some(ChatMemberKind::Banned(_))
+    );
+    // Unbanned users have to join the chat again. Without `only_if_banned`, Telegram removes
+    // the user from the chat even if they weren't banned
+    if is_banned || !body.only_if_banned.unwrap_or(false) {
+        lock.chat_members.insert(member, ChatMemberKind::Left);
+    }
     lock.responses.unbanned_chat_members.push(body.into_inner());
     Ok(make_telegram_result(true))
 }
@@ -0,0 +1,94 @@
+//! A pool of fake servers that are started ahead of time
+//!
+//! Starting a server is the slowest part of `MockBot::new`, so suites that make a lot of bots
+//! can start the servers up front, and hand them out to the bots when they are needed.
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use teloxide::dispatching::UpdateHandler;
+
+use crate::{
+    dataset::IntoUpdate,
+    mock_bot::{DistributionKey, MockBot},
+    server::ServerManager,
+    state::State,
+    utils::default_distribution_function,
+};
+
+/// A fake server that was started ahead of time, with the state it serves
+struct WarmServer {
+    state: Arc<Mutex<State>>,
+    server: ServerManager,
+}
+
+/// A pool of already running fake servers, each of them is handed out to one bot.
+///
+/// The servers run on the tokio runtime they were started on, so the pool should be used inside
+/// of one test, and not shared between `#[tokio::test]`s, which have a runtime each.
+///
+/// # Example
+///
+/// ```ignore
+/// let pool = ServerPool::warm(2).await;
+/// let mut first = pool.bot(MockMessageText::new().text("Hi!"), handler_tree()).await;
+/// let mut second = pool.bot(MockMessageText::new().text("Bye!"), handler_tree()).await;
+/// assert_eq!(pool.available(), 0);
+/// ```
+#[derive(Default)]
+pub struct ServerPool {
+    servers: Mutex<Vec<WarmServer>>,
+}
+
+impl ServerPool {
+    /// Creates a pool with `count` servers started and ready
+    pub async fn warm(count: usize) -> Self {
+        let pool = Self::default();
+        pool.add(count).await;
+        pool
+    }
+
+    /// Starts `count` more servers and adds them to the pool
+    pub async fn add(&self, count: usize) {
+        for _ in 0..count {
+            let state = Arc::new(Mutex::new(State::from_env()));
+            let server = ServerManager::start(state.clone())
+                .await
+                .expect("Failed to start mock server");
+            self.servers
+                .lock()
+                .unwrap()
+                .push(WarmServer { state, server });
+        }
+    }
+
+    /// Returns how many started servers are left in the pool
+    pub fn available(&self) -> usize {
+        self.servers.lock().unwrap().len()
+    }
+
+    /// Creates a bot, the same way as `MockBot::new`, but on a server from the pool. If the pool
+    /// is empty, a new server is started, so it never fails.
+    pub async fn bot<Err, T>(
+        &self,
+        update: T,
+        handler_tree: UpdateHandler<Err>,
+    ) -> MockBot<Err, DistributionKey>
+    where
+        Err: Debug + Send + Sync + 'static,
+        T: IntoUpdate,
+    {
+        let warm = self.servers.lock().unwrap().pop();
+        match warm {
+            Some(WarmServer { state, server }) => MockBot::with_server(
+                update,
+                handler_tree,
+                default_distribution_function,
+                state,
+                server,
+            ),
+            None => MockBot::new(update, handler_tree).await,
+        }
+    }
+}
@@ -1,9 +1,18 @@
+use std::{collections::HashMap, env, time::Duration};
+
+use chrono::{DateTime, FixedOffset, Utc};
 use teloxide::{
     prelude::*,
-    types::{File, FileMeta, MessageId, MessageKind},
+    types::{
+        Chat, ChatMemberKind, ChatPermissions, File, FileMeta, Me, MediaKind, MessageCommon,
+        MessageId, MessageKind, MessageOrigin, Poll, Seconds,
+    },
 };
 
-use crate::{server::messages::Messages, MockMessageText, Responses};
+use crate::{
+    server::messages::Messages, MockChannelChat, MockMe, MockMessageText, MockSupergroupChat,
+    MockUser, Responses,
+};
 
 /// Extract file metadata directly from message fields without JSON serialization.
 /// This is more efficient than serializing the entire message to JSON.
@@ -37,20 +46,122 @@ fn extract_file_meta(message: &Message) -> Option<FileMeta> {
     None
 }
 
+/// The id of the service account that automatic forwards are sent from
+const TELEGRAM_USER_ID: u64 = 777000;
+
 /// Default file path used when storing files from messages.
 /// The actual path doesn't matter for testing purposes.
 const DEFAULT_FILE_PATH: &str = "some_path.txt";
 
-#[derive(Default)]
 pub(crate) struct State {
+    /// The bot the server is pretending to be, returned by `get_me` and used as the sender
+    pub me: Me,
     pub files: Vec<File>,
     pub responses: Responses,
     pub messages: Messages,
+    /// The amount of requests the server has received, but not yet responded to
+    pub in_flight: usize,
+    /// One line per request the server handled during the last dispatch, taken with
+    /// `MockBot::take_request_log`
+    pub request_log: Vec<String>,
+    /// Whether the raw bodies of requests are stored, set with `MockBot::capture_raw_requests`
+    pub capture_raw_requests: bool,
+    /// The default permissions of chats, set with `set_chat_permissions`. Kept between dispatches
+    pub chat_permissions: HashMap<i64, ChatPermissions>,
+    /// The sticker sets of supergroups, set with `set_chat_sticker_set`. Kept between dispatches
+    pub chat_sticker_sets: HashMap<i64, String>,
+    /// The id of the next sent poll, if poll ids are seeded with `MockBot::seed_poll_ids`
+    pub next_poll_id: Option<u64>,
+    /// Channels and their discussion groups, linked with `MockBot::link_chats`
+    pub linked_chats: HashMap<i64, i64>,
+    /// The chats registered with `MockBot::register_chat`, by id. Messages sent to them carry
+    /// their title and username. Kept between dispatches
+    pub chats: HashMap<i64, Chat>,
+    /// The slow mode delays of supergroups, set with `MockBot::slow_mode`
+    pub slow_mode_delays: HashMap<i64, Seconds>,
+    /// The fixed time of the server, set with `MockBot::set_clock_tz`. The real time if not set
+    pub clock: Option<DateTime<FixedOffset>>,
+    /// The statuses of users in chats, by chat id and user id, changed with `ban_chat_member`
+    /// and `unban_chat_member`. Users that aren't here are regular members. Kept between
+    /// dispatches
+    pub chat_members: HashMap<(i64, u64), ChatMemberKind>,
+    /// How long the server waits before handling every request, set with `MockBot::latency`
+    pub latency: Duration,
+    /// Whether calls to unimplemented endpoints fail the test, set with `MockBot::strict`
+    pub strict: bool,
+    /// The unimplemented endpoints the bot called during the last dispatch, in strict mode
+    pub unimplemented_calls: Vec<String>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            me: MockMe::new().build(),
+            files: Vec::new(),
+            responses: Responses::default(),
+            messages: Messages::default(),
+            in_flight: 0,
+            request_log: Vec::new(),
+            capture_raw_requests: false,
+            chat_permissions: HashMap::new(),
+            chat_sticker_sets: HashMap::new(),
+            next_poll_id: None,
+            linked_chats: HashMap::new(),
+            chats: HashMap::new(),
+            slow_mode_delays: HashMap::new(),
+            clock: None,
+            chat_members: HashMap::new(),
+            latency: Duration::ZERO,
+            strict: false,
+            unimplemented_calls: Vec::new(),
+        }
+    }
 }
 
 impl State {
+    /// The default state, with the latency and strict mode taken from the `TEREMOCK_LATENCY_MS`
+    /// and `TEREMOCK_STRICT` environment variables, if they are set
+    pub fn from_env() -> Self {
+        Self {
+            latency: Self::latency_from(env::var("TEREMOCK_LATENCY_MS").ok().as_deref()),
+            strict: env::var("TEREMOCK_STRICT")
+                .is_ok_and(|strict| strict == "1" || strict == "true"),
+            ..Self::default()
+        }
+    }
+
+    /// Parses the latency from the value of `TEREMOCK_LATENCY_MS`, in milliseconds. No latency
+    /// if there is no value, or it isn't a number
+    pub(crate) fn latency_from(ms: Option<&str>) -> Duration {
+        ms.and_then(|ms| ms.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or_default()
+    }
+
     pub fn reset(&mut self) {
         self.responses = Responses::default();
+        self.request_log.clear();
+        self.unimplemented_calls.clear();
+    }
+
+    /// The current time of the server, the fixed clock if there is one
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.clock
+            .map(|clock| clock.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now)
+    }
+
+    /// Stores a message sent by the bot, dated with the current time of the server
+    pub(crate) fn add_sent_message(&mut self, mut message: Message) -> Message {
+        message.date = self.now();
+        self.messages.add_message(message)
+    }
+
+    /// Marks the message as edited right now, like Telegram does on every edit
+    pub(crate) fn set_edit_date(&mut self, message_id: i32) -> Option<Message> {
+        let now = self.now();
+        self.messages
+            .edit_message_field(message_id, "edit_date", now.timestamp())
     }
 
     pub(crate) fn add_message(&mut self, message: &mut Message) {
@@ -119,4 +230,63 @@ impl State {
         log::debug!("Edited message with {}.", message.id);
         self.messages.edit_message(message.clone());
     }
+
+    /// Updates the stored poll messages with the new state of the poll, like it being closed
+    pub(crate) fn update_poll(&mut self, poll: &Poll) {
+        for message in self.messages.messages.iter_mut() {
+            if let MessageKind::Common(MessageCommon {
+                media_kind: MediaKind::Poll(ref mut media),
+                ..
+            }) = message.kind
+            {
+                if media.poll.id == poll.id {
+                    media.poll = poll.clone();
+                }
+            }
+        }
+    }
+
+    /// Stores the copy of a channel post that Telegram automatically forwards to the linked
+    /// discussion group, if the channel has one
+    pub(crate) fn auto_forward(&mut self, message: &Message) {
+        let Some(&group_id) = self.linked_chats.get(&message.chat.id.0) else {
+            return;
+        };
+        let channel = MockChannelChat::new().id(message.chat.id.0).build();
+
+        let mut forward = message.clone();
+        forward.id = MessageId(self.messages.max_message_id() + 1);
+        forward.chat = MockSupergroupChat::new().id(group_id).build();
+        forward.from = Some(
+            MockUser::new()
+                .id(TELEGRAM_USER_ID)
+                .first_name("Telegram")
+                .build(),
+        );
+        forward.sender_chat = Some(channel.clone());
+        if let MessageKind::Common(ref mut common) = forward.kind {
+            common.forward_origin = Some(MessageOrigin::Channel {
+                date: message.date,
+                chat: channel,
+                message_id: message.id,
+                author_signature: None,
+            });
+            common.is_automatic_forward = true;
+        }
+
+        let forward = self.messages.add_message(forward);
+        self.responses.automatic_forwards.push(forward);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_from() {
+        assert_eq!(State::latency_from(Some("100")), Duration::from_millis(100));
+        assert_eq!(State::latency_from(Some("fast")), Duration::ZERO);
+        assert_eq!(State::latency_from(None), Duration::ZERO);
+    }
 }
@@ -22,8 +22,8 @@ use teloxide::{
     types::{
         BotCommand, ChatAction, ChatPermissions, DiceEmoji, InlineKeyboardButton,
         InlineKeyboardMarkup, InputFile, InputMedia, InputMediaAudio, InputMediaDocument,
-        InputMediaPhoto, InputMediaVideo, LabeledPrice, LinkPreviewOptions, Message, MessageEntity,
-        MessageId, PollOption, PollType, ReactionType, ReplyParameters, Update,
+        InputMediaPhoto, InputMediaVideo, LabeledPrice, LinkPreviewOptions, LivePeriod, Message,
+        MessageEntity, MessageId, PollOption, PollType, ReactionType, ReplyParameters, Update,
     },
 };
 
@@ -1201,3 +1201,3757 @@ async fn test_multi_dispatch_persistent_server() {
     let third_response = bot.get_responses().sent_messages.pop().unwrap();
     assert_eq!(third_response.text(), Some("/echo third"));
 }
+
+//
+// Business messages
+//
+
+async fn business_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let is_from_offline = match &msg.kind {
+        teloxide::types::MessageKind::Common(common) => common.is_from_offline,
+        _ => false,
+    };
+    let text = if is_from_offline { "offline" } else { "online" };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+fn get_business_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_business_message().endpoint(business_handler)
+}
+
+#[tokio::test]
+async fn test_business_message_is_from_offline() {
+    let message = MockMessageText::new().text("away").build();
+    let mut bot = MockBot::new(
+        MockBusinessMessage::new(message.clone()).is_from_offline(true),
+        get_business_schema(),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("offline"));
+
+    bot.update(MockBusinessMessage::new(message));
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("online"));
+}
+
+async fn three_messages_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    for text in ["first", "second", "third"] {
+        bot.send_message(msg.chat.id, text).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sent_messages_order() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(three_messages_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let texts: Vec<_> = responses
+        .sent_messages
+        .iter()
+        .map(|message| message.text().unwrap())
+        .collect();
+    assert_eq!(texts, vec!["first", "second", "third"]);
+    assert!(responses
+        .sent_messages
+        .windows(2)
+        .all(|pair| pair[0].id.0 < pair[1].id.0));
+}
+
+async fn staleness_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let text = if Utc::now() - msg.date > chrono::Duration::hours(1) {
+        "stale"
+    } else {
+        "fresh"
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_message_date() {
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("hi")
+            .date(Utc::now() - chrono::Duration::days(365)),
+        Update::filter_message().endpoint(staleness_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("stale"));
+
+    bot.update(MockMessageText::new().text("hi"));
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("fresh"));
+}
+
+async fn paid_media_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("paid.jpg");
+    bot.send_paid_media(
+        msg.chat.id,
+        25,
+        vec![teloxide::types::InputPaidMedia::Photo(
+            teloxide::types::InputPaidMediaPhoto::new(photo),
+        )],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_paid_media() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(paid_media_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent_paid_media = responses.sent_messages_paid_media.last().unwrap();
+    let paid_media = sent_paid_media.message.paid_media().unwrap();
+    assert_eq!(paid_media.star_count, 25);
+    assert_eq!(paid_media.paid_media.len(), 1);
+    assert_eq!(sent_paid_media.bot_request.star_count, 25);
+    assert_eq!(sent_paid_media.bot_request.media.len(), 1);
+}
+
+async fn paid_media_channel_handler(
+    bot: Bot,
+    _msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("paid.jpg");
+    bot.send_paid_media(
+        teloxide::types::Recipient::ChannelUsername("@teremock_news".to_string()),
+        25,
+        vec![teloxide::types::InputPaidMedia::Photo(
+            teloxide::types::InputPaidMediaPhoto::new(photo),
+        )],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_paid_media_to_channel_username() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(paid_media_channel_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent_paid_media = responses.sent_messages_paid_media.last().unwrap();
+    assert!(sent_paid_media.message.chat.is_channel());
+    assert_eq!(
+        sent_paid_media.message.chat.username(),
+        Some("teremock_news")
+    );
+}
+
+async fn voice_parse_mode_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let voice = InputFile::memory("somedata".to_string()).file_name("test.mp3");
+    bot.send_voice(msg.chat.id, voice)
+        .caption("<b>bold</b> and <i>italic</i>")
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_voice_parse_mode() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(voice_parse_mode_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot
+        .get_responses()
+        .sent_messages_voice
+        .pop()
+        .unwrap()
+        .message;
+    assert_eq!(last_sent_message.caption(), Some("bold and italic"));
+    assert_eq!(
+        last_sent_message.caption_entities().unwrap(),
+        &[MessageEntity::bold(0, 4), MessageEntity::italic(9, 6)]
+    );
+}
+
+async fn parse_mode_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let sent = bot
+        .send_message(msg.chat.id, "<b>bold</b> text")
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+    bot.edit_message_text(msg.chat.id, sent.id, "*new* _text_")
+        .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+        .await?;
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    bot.send_photo(msg.chat.id, photo)
+        .caption("<i>photo</i>")
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+    let media = || InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    bot.send_media_group(
+        msg.chat.id,
+        vec![
+            InputMedia::Photo(
+                InputMediaPhoto::new(media())
+                    .caption("<u>first</u>")
+                    .parse_mode(teloxide::types::ParseMode::Html),
+            ),
+            InputMedia::Photo(InputMediaPhoto::new(media())),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parse_mode_in_every_route() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(parse_mode_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = &responses.sent_messages_text[0];
+    assert_eq!(sent.message.text(), Some("bold text"));
+    assert_eq!(
+        sent.message.entities().unwrap(),
+        &[MessageEntity::bold(0, 4)]
+    );
+    // The request is kept as the bot sent it
+    assert_eq!(sent.bot_request.text, "<b>bold</b> text");
+
+    let edited = &responses.edited_messages_text[0].message;
+    assert_eq!(edited.text(), Some("new text"));
+    assert_eq!(
+        edited.entities().unwrap(),
+        &[MessageEntity::bold(0, 3), MessageEntity::italic(4, 4)]
+    );
+
+    let photo = &responses.sent_messages_photo[0].message;
+    assert_eq!(photo.caption(), Some("photo"));
+    assert_eq!(
+        photo.caption_entities().unwrap(),
+        &[MessageEntity::italic(0, 5)]
+    );
+
+    let group = &responses.sent_media_group[0].messages;
+    assert_eq!(group[0].caption(), Some("first"));
+    assert_eq!(
+        group[0].caption_entities().unwrap(),
+        &[MessageEntity::underline(0, 5)]
+    );
+}
+
+#[tokio::test]
+async fn test_pending_updates() {
+    let mut bot = MockBot::new(
+        vec![
+            MockMessageText::new().text("/echo first"),
+            MockMessageText::new().text("/echo second"),
+        ],
+        get_schema(),
+    )
+    .await;
+
+    let pending = bot.pending_updates();
+    assert_eq!(pending.len(), 2);
+    assert!(matches!(
+        &pending[1].kind,
+        teloxide::types::UpdateKind::Message(message) if message.text() == Some("/echo second")
+    ));
+    assert!(bot.get_responses().sent_messages.is_empty());
+
+    bot.update(MockCallbackQuery::new().data("test"));
+    assert!(matches!(
+        bot.pending_updates()[0].kind,
+        teloxide::types::UpdateKind::CallbackQuery(_)
+    ));
+}
+
+async fn edit_caption_markup_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    let photo_message = bot
+        .send_photo(msg.chat.id, photo)
+        .caption("before")
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("old", "old"),
+        ]]))
+        .await?;
+    bot.edit_message_caption(msg.chat.id, photo_message.id)
+        .caption("after")
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("new", "new"),
+        ]]))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_edit_caption_reply_markup() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(edit_caption_markup_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let edited = bot.get_responses().edited_messages_caption.pop().unwrap();
+    assert_eq!(edited.message.caption(), Some("after"));
+    assert_eq!(
+        edited.message.reply_markup().unwrap().inline_keyboard[0][0].text,
+        "new"
+    );
+    assert!(edited.bot_request.reply_markup.is_some());
+}
+
+async fn entities_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // "😀" is two UTF-16 code units, so a bold entity of 3 units fits exactly
+    bot.send_message(msg.chat.id, "😀a")
+        .entities(vec![MessageEntity::bold(0, 3)])
+        .await?;
+    bot.send_message(msg.chat.id, "😀a")
+        .entities(vec![MessageEntity::bold(0, 4)])
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_message_entities_out_of_bounds() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(entities_handler),
+    )
+    .await;
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_text.len(), 1);
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("CantParseEntities"));
+}
+
+async fn forum_topic_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    if let Some(topic) = msg.forum_topic_created() {
+        bot.send_message(msg.chat.id, format!("Welcome to {}!", topic.name))
+            .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forum_topic_created() {
+    let mut bot = MockBot::new(
+        MockMessageForumTopicCreated::new()
+            .name("Announcements")
+            .chat(MockSupergroupChat::new().is_forum(true).build()),
+        Update::filter_message().endpoint(forum_topic_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("Welcome to Announcements!"));
+}
+
+#[tokio::test]
+async fn test_assert_text() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo echo"), get_schema()).await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "/echo echo");
+    crate::assert_text!(bot, String::from("/echo echo"));
+}
+
+#[tokio::test]
+#[should_panic(expected = "sent_messages: 1")]
+async fn test_assert_text_mismatch() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo echo"), get_schema()).await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "something else");
+}
+
+async fn giveaway_winners_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    if let Some(winners) = msg.giveaway_winners() {
+        bot.send_message(
+            msg.chat.id,
+            format!("{} winners were selected", winners.winner_count),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_giveaway_winners() {
+    let mut bot = MockBot::new(
+        MockMessageGiveawayWinners::new().winner_count(3u32),
+        Update::filter_message().endpoint(giveaway_winners_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("3 winners were selected"));
+}
+
+async fn spawned_send_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    tokio::spawn(async move {
+        bot.send_message(msg.chat.id, "From the background")
+            .await
+            .unwrap();
+    });
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_await_quiescence() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(spawned_send_handler),
+    )
+    .await;
+    // Keeps the background request in flight for a while after the handler is done
+    bot.latency(std::time::Duration::from_millis(50));
+
+    bot.dispatch().await;
+    bot.await_quiescence().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("From the background"));
+}
+
+async fn delayed_send_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    tokio::spawn(async move {
+        // Well within the idle period of `await_quiescence`
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        bot.send_message(msg.chat.id, "After a while")
+            .await
+            .unwrap();
+    });
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_await_quiescence_late_request() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(delayed_send_handler),
+    )
+    .await;
+    bot.latency(std::time::Duration::from_millis(50));
+
+    bot.dispatch().await;
+    // The request hasn't reached the server yet when the wait begins
+    assert_eq!(bot.in_flight(), 0);
+    assert!(bot.get_responses().sent_messages.is_empty());
+    bot.await_quiescence().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("After a while"));
+}
+
+async fn boost_added_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    if let Some(boost) = msg.boost_added() {
+        bot.send_message(
+            msg.chat.id,
+            format!("Thanks for {} boosts!", boost.boost_count),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_boost_added() {
+    let mut bot = MockBot::new(
+        MockMessageBoostAdded::new().boost_count(4u16),
+        Update::filter_message().endpoint(boost_added_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(last_response.text(), Some("Thanks for 4 boosts!"));
+}
+
+async fn bad_reply_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "Reply")
+        .reply_parameters(ReplyParameters::new(MessageId(404)))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_take_request_log() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(bad_reply_handler),
+    )
+    .await;
+    bot.error_handler(Arc::new(MyErrorHandler::new()));
+
+    bot.dispatch().await;
+
+    let request_log = bot.take_request_log();
+    assert!(request_log
+        .iter()
+        .any(|line| line.starts_with("SendMessage 400")
+            && line.contains("message to reply not found")));
+    assert!(bot.take_request_log().is_empty());
+}
+
+async fn copy_captioned_video_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let video = bot
+        .send_video(
+            msg.chat.id,
+            InputFile::memory("somedata".to_string()).file_name("video.mp4"),
+        )
+        .caption("original caption")
+        .caption_entities(vec![MessageEntity::bold(0, 8)])
+        .await?;
+    bot.copy_message(msg.chat.id, msg.chat.id, video.id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_message_keeps_caption() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(copy_captioned_video_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let copied_message = responses.sent_messages.last().unwrap();
+    assert!(copied_message.video().is_some());
+    assert_eq!(copied_message.caption(), Some("original caption"));
+    assert_eq!(
+        copied_message.caption_entities(),
+        Some(&[MessageEntity::bold(0, 8)][..])
+    );
+}
+
+async fn slot_machine_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    for _ in 0..20 {
+        bot.send_dice(msg.chat.id)
+            .emoji(DiceEmoji::SlotMachine)
+            .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_dice_slot_machine_range() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(slot_machine_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_dice.len(), 20);
+    for sent in responses.sent_messages_dice {
+        let dice = sent.message.dice().unwrap();
+        assert_eq!(dice.emoji, DiceEmoji::SlotMachine);
+        assert!((1..=64).contains(&dice.value));
+    }
+}
+
+async fn send_many_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let count: usize = msg.text().unwrap().parse()?;
+    for i in 0..count {
+        bot.send_message(msg.chat.id, i.to_string()).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stored_message_count() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("0"),
+        Update::filter_message().endpoint(send_many_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    let before = bot.stored_message_count();
+
+    bot.update(MockMessageText::new().text("3"));
+    bot.dispatch().await;
+
+    // The incoming message is stored too
+    assert_eq!(bot.stored_message_count(), before + 1 + 3);
+}
+
+async fn external_reply_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let group_id = ChatId(-1001234567890);
+    let photo = bot
+        .send_photo(
+            group_id,
+            InputFile::memory("somedata".to_string()).file_name("photo.jpg"),
+        )
+        .await?;
+
+    let mut reply_parameters = ReplyParameters::new(photo.id);
+    reply_parameters.chat_id = Some(teloxide::types::Recipient::Id(group_id));
+    bot.send_message(msg.chat.id, "Look at this")
+        .reply_parameters(reply_parameters)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_external_reply() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(external_reply_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let reply = responses.sent_messages.last().unwrap();
+    assert!(reply.reply_to_message().is_none());
+    let teloxide::types::MessageKind::Common(common) = &reply.kind else {
+        panic!("Expected a common message");
+    };
+    let external_reply = common.external_reply.as_ref().unwrap();
+    assert_eq!(
+        external_reply.chat.as_ref().map(|chat| chat.id),
+        Some(ChatId(-1001234567890))
+    );
+    assert_eq!(
+        external_reply.message_id,
+        Some(responses.sent_messages_photo[0].message.id)
+    );
+    assert!(matches!(
+        external_reply.kind,
+        teloxide::types::ExternalReplyInfoKind::Photo(_)
+    ));
+}
+
+async fn external_reply_everywhere_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let group_id = ChatId(-1001234567890);
+    let note = bot.send_message(group_id, "Note").await?;
+    let photo = bot
+        .send_photo(
+            group_id,
+            InputFile::memory("somedata".to_string()).file_name("photo.jpg"),
+        )
+        .await?;
+
+    let mut note_parameters = ReplyParameters::new(note.id);
+    note_parameters.chat_id = Some(teloxide::types::Recipient::Id(group_id));
+    let mut photo_parameters = ReplyParameters::new(photo.id);
+    photo_parameters.chat_id = Some(teloxide::types::Recipient::Id(group_id));
+    bot.send_message(msg.chat.id, "About the note")
+        .reply_parameters(note_parameters)
+        .await?;
+    bot.send_media_group(
+        msg.chat.id,
+        vec![
+            InputMedia::Photo(InputMediaPhoto::new(
+                InputFile::memory("1").file_name("1.jpg"),
+            )),
+            InputMedia::Photo(InputMediaPhoto::new(
+                InputFile::memory("2").file_name("2.jpg"),
+            )),
+        ],
+    )
+    .reply_parameters(photo_parameters.clone())
+    .await?;
+    bot.copy_message(msg.chat.id, group_id, photo.id)
+        .reply_parameters(photo_parameters)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_external_reply_in_every_route() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(external_reply_everywhere_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let external_reply = |message: &Message| {
+        let teloxide::types::MessageKind::Common(common) = &message.kind else {
+            panic!("Expected a common message");
+        };
+        common.external_reply.clone()
+    };
+
+    // teloxide has no external reply for text, but the reply still can't point into another chat
+    let text_reply = &responses.sent_messages_text[1].message;
+    assert!(text_reply.reply_to_message().is_none());
+    assert_eq!(external_reply(text_reply), None);
+
+    let photo_id = responses.sent_messages_photo[0].message.id;
+    let copy = responses.sent_messages.last().unwrap();
+    let album = &responses.sent_media_group[0].messages;
+    for message in album.iter().chain([copy]) {
+        assert!(message.reply_to_message().is_none());
+        let external_reply = external_reply(message).unwrap();
+        assert_eq!(external_reply.message_id, Some(photo_id));
+        assert!(matches!(
+            external_reply.kind,
+            teloxide::types::ExternalReplyInfoKind::Photo(_)
+        ));
+        assert!(matches!(
+            external_reply.origin,
+            teloxide::types::MessageOrigin::User { .. }
+        ));
+    }
+}
+
+async fn paid_broadcast_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "Broadcast")
+        .allow_paid_broadcast(true)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_message_allow_paid_broadcast() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(paid_broadcast_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages_text.last().unwrap();
+    assert_eq!(sent.bot_request.allow_paid_broadcast, Some(true));
+}
+
+async fn edit_channel_post_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.edit_message_text(msg.chat.id, msg.id, "Edited post")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_edit_channel_post_keeps_author_signature() {
+    let post = MockMessageText::new()
+        .text("Post")
+        .chat(MockChannelChat::new().build())
+        .author_signature("Author")
+        .build();
+    let mut bot = MockBot::new(
+        MockChannelPost::new(post),
+        Update::filter_channel_post().endpoint(edit_channel_post_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let edited = &responses.edited_messages_text.last().unwrap().message;
+    assert_eq!(edited.text(), Some("Edited post"));
+    assert_eq!(edited.author_signature(), Some("Author"));
+}
+
+async fn two_chats_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "To the user").await?;
+    bot.send_message(ChatId(-100), "To the group").await?;
+    bot.send_message(msg.chat.id, "To the user again").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_responses_view_to_chat() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(two_chats_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.view().to(UserId(MockUser::ID)).texts(),
+        vec!["To the user", "To the user again"]
+    );
+    assert_eq!(responses.view().to(-100).texts(), vec!["To the group"]);
+    assert!(responses.view().photos().messages().is_empty());
+}
+
+async fn live_location_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let location = bot
+        .send_location(msg.chat.id, 1.0, 1.0)
+        .live_period(LivePeriod::from_u32(60))
+        .await?;
+    bot.edit_message_live_location(msg.chat.id, location.id, 2.0, 2.0)
+        .await?;
+    bot.stop_message_live_location(msg.chat.id, location.id)
+        .await?;
+    // A stopped live location can't be edited anymore
+    let result = bot
+        .edit_message_live_location(msg.chat.id, location.id, 3.0, 3.0)
+        .await;
+    assert!(matches!(
+        result,
+        Err(teloxide::RequestError::Api(
+            teloxide::ApiError::MessageCantBeEdited
+        ))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stop_message_live_location() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(live_location_handler),
+    )
+    .await;
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    assert!(error_handler.errors().is_empty());
+    let responses = bot.get_responses();
+    let location_id = responses.sent_messages_location[0].message.id;
+
+    assert_eq!(responses.edited_messages_live_location.len(), 1);
+    let edited = &responses.edited_messages_live_location[0].message;
+    assert_eq!(edited.location().unwrap().latitude, 2.0);
+
+    assert_eq!(responses.stopped_live_locations.len(), 1);
+    let stopped = &responses.stopped_live_locations[0];
+    assert_eq!(stopped.message.id, location_id);
+    assert_eq!(stopped.bot_request.message_id, Some(location_id.0));
+}
+
+async fn live_location_heading_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    use teloxide::payloads::{EditMessageLiveLocationSetters, SendLocationSetters};
+
+    let location = bot
+        .send_location(msg.chat.id, 1.0, 1.0)
+        .live_period(LivePeriod::from_u32(60))
+        .proximity_alert_radius(100)
+        .await?;
+    // Only the heading is new, which is still a change
+    bot.edit_message_live_location(msg.chat.id, location.id, 1.0, 1.0)
+        .heading(90)
+        .await?;
+    bot.edit_message_live_location(msg.chat.id, location.id, 1.0, 1.0)
+        .heading(90)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_edit_live_location_heading() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(live_location_heading_handler),
+    )
+    .await;
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    // The same edit again didn't change anything
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0], "Api(MessageNotModified)");
+    let responses = bot.get_responses();
+    assert_eq!(responses.edited_messages_live_location.len(), 1);
+    let location = responses.edited_messages_live_location[0]
+        .message
+        .location()
+        .unwrap();
+    assert_eq!(location.heading, Some(90));
+    // The proximity alerts weren't changed, so they are still on
+    assert_eq!(location.proximity_alert_radius, Some(100));
+}
+
+async fn get_big_file_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let result = bot.get_file(msg.document().unwrap().file.id.clone()).await;
+    match result {
+        Err(teloxide::RequestError::Api(teloxide::ApiError::Unknown(error))) => {
+            bot.send_message(msg.chat.id, error).await?;
+        }
+        _ => {
+            bot.send_message(msg.chat.id, "Got the file").await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_file_too_big() {
+    let mut bot = MockBot::new(
+        MockMessageDocument::new().file_size(25 * 1024 * 1024u32),
+        Update::filter_message().endpoint(get_big_file_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    crate::assert_text!(bot, "Bad Request: file is too big");
+
+    bot.update(
+        MockMessageDocument::new()
+            .file_id(teloxide::types::FileId("small_file_id".to_owned()))
+            .file_size(1024u32),
+    );
+    bot.dispatch().await;
+    crate::assert_text!(bot, "Got the file");
+}
+
+#[tokio::test]
+async fn test_answer_callback_query_business_connection_id() {
+    let bot = MockBot::new(
+        MockCallbackQuery::new(),
+        Update::filter_callback_query().endpoint(|| async { Ok::<(), HandlerError>(()) }),
+    )
+    .await;
+
+    // Teloxide has no setter for this field, so the request is made by hand
+    let url = format!(
+        "{}bot{}/AnswerCallbackQuery",
+        bot.api_url(),
+        bot.bot.token()
+    );
+    let response = teloxide::net::client_from_env()
+        .post(url)
+        .json(&serde_json::json!({
+            "callback_query_id": "123",
+            "business_connection_id": "business_connection",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let responses = bot.get_responses();
+    let answered = responses.answered_callback_queries.last().unwrap();
+    assert_eq!(
+        answered.business_connection_id,
+        Some(teloxide::types::BusinessConnectionId(
+            "business_connection".to_owned()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn test_set_handler() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo echo"), get_schema()).await;
+
+    bot.dispatch().await;
+    crate::assert_text!(bot, "/echo echo");
+
+    bot.set_handler(
+        Update::filter_message().endpoint(|bot: Bot, msg: Message| async move {
+            bot.send_message(msg.chat.id, "New handler").await?;
+            Ok(())
+        }),
+    );
+    bot.dispatch().await;
+    crate::assert_text!(bot, "New handler");
+}
+
+async fn caption_above_media_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("photo.jpg");
+    bot.send_photo(msg.chat.id, photo.clone())
+        .caption("Above")
+        .show_caption_above_media(true)
+        .await?;
+    bot.send_media_group(
+        msg.chat.id,
+        vec![
+            InputMedia::Photo(
+                InputMediaPhoto::new(photo.clone())
+                    .caption("Above")
+                    .show_caption_above_media(true),
+            ),
+            InputMedia::Photo(InputMediaPhoto::new(photo)),
+        ],
+    )
+    .await?;
+    // Documents don't support the flag, but sending them with a caption still works
+    bot.send_document(
+        msg.chat.id,
+        InputFile::memory("somedata".to_string()).file_name("file.txt"),
+    )
+    .caption("Below")
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_show_caption_above_media() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(caption_above_media_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let show_above = |message: &Message| match &message.kind {
+        teloxide::types::MessageKind::Common(common) => match &common.media_kind {
+            teloxide::types::MediaKind::Photo(photo) => photo.show_caption_above_media,
+            _ => false,
+        },
+        _ => false,
+    };
+    assert!(show_above(&responses.sent_messages_photo[0].message));
+    let media_group = &responses.sent_media_group[0].messages;
+    assert!(show_above(&media_group[0]));
+    assert!(!show_above(&media_group[1]));
+    assert_eq!(
+        responses.sent_messages_document[0].message.caption(),
+        Some("Below")
+    );
+}
+
+#[crate::test(update = MockMessageText::new().text("/echo echo"), tree = get_schema())]
+async fn test_test_macro(mut bot: MockBot<HandlerError, DistributionKey>) {
+    bot.dispatch().await;
+    crate::assert_text!(bot, "/echo echo");
+}
+
+async fn spoiler_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_photo(
+        msg.chat.id,
+        InputFile::memory("somedata".to_string()).file_name("photo.jpg"),
+    )
+    .has_spoiler(true)
+    .await?;
+    bot.send_video(
+        msg.chat.id,
+        InputFile::memory("somedata".to_string()).file_name("video.mp4"),
+    )
+    .has_spoiler(true)
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_photo_has_media_spoiler() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(spoiler_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages_photo[0].message.has_media_spoiler());
+}
+
+#[tokio::test]
+async fn test_video_has_media_spoiler() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        Update::filter_message().endpoint(spoiler_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages_video[0].message.has_media_spoiler());
+}
+
+#[tokio::test]
+async fn test_last_sender_is_bot() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/dice"), get_schema()).await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages.last().unwrap().dice().is_some());
+    let sender = responses.last_sender().unwrap();
+    assert_eq!(sender.id, bot.me.user.id);
+    assert!(sender.is_bot);
+}
+
+async fn reply_to_user_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "Not a reply").await?;
+    bot.send_message(msg.chat.id, "A reply")
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_reply_to_message_id() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().id(42),
+        Update::filter_message().endpoint(reply_to_user_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.last_reply_to_message_id(), Some(42));
+}
+
+async fn chat_kind_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let kind = if msg.chat.is_supergroup() {
+        "supergroup"
+    } else {
+        "other"
+    };
+    bot.send_message(msg.chat.id, kind).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_chat_kind() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("Hi!"),
+        Update::filter_message().endpoint(chat_kind_handler),
+    )
+    .await;
+    bot.default_chat_kind(MockSupergroupChat::new().build().kind);
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "supergroup");
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.sent_messages.last().unwrap().chat.id,
+        ChatId(MockSupergroupChat::ID)
+    );
+}
+
+async fn edit_caption_html_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    let photo_message = bot.send_photo(msg.chat.id, photo).caption("before").await?;
+    bot.edit_message_caption(msg.chat.id, photo_message.id)
+        .caption("<b>bold</b> caption")
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_edit_caption_parse_mode_entities() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(edit_caption_html_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let edited = bot.get_responses().edited_messages_caption.pop().unwrap();
+    assert_eq!(edited.message.caption(), Some("bold caption"));
+    assert_eq!(
+        edited.message.caption_entities(),
+        Some([MessageEntity::bold(0, 4)].as_slice())
+    );
+}
+
+async fn chat_permissions_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.set_chat_permissions(msg.chat.id, ChatPermissions::SEND_MESSAGES)
+        .await?;
+    let chat = bot.get_chat(msg.chat.id).await?;
+    let text = match chat.permissions() {
+        Some(permissions) if permissions == ChatPermissions::SEND_MESSAGES => "only messages",
+        Some(_) => "other permissions",
+        None => "no permissions",
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_chat_reflects_permissions() {
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("hi")
+            .chat(MockSupergroupChat::new().build()),
+        Update::filter_message().endpoint(chat_permissions_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "only messages");
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.set_chat_permissions[0].permissions,
+        ChatPermissions::SEND_MESSAGES
+    );
+}
+
+async fn forward_channel_post_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.forward_message(UserId(MockUser::ID), msg.chat.id, msg.id)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forward_channel_post_sets_sender_chat() {
+    let channel = MockChannelChat::new().build();
+    let post = MockMessageText::new()
+        .text("Post")
+        .chat(channel.clone())
+        .build();
+    let mut bot = MockBot::new(
+        MockChannelPost::new(post),
+        Update::filter_channel_post().endpoint(forward_channel_post_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let forwarded = &responses.forwarded_messages.last().unwrap().message;
+    assert_eq!(forwarded.sender_chat, Some(channel));
+    assert_eq!(
+        forwarded.from.as_ref().map(|user| user.id),
+        Some(bot.me.user.id)
+    );
+}
+
+fn chat_distribution_function(update: &Update) -> Option<ChatId> {
+    update.chat().map(|chat| chat.id)
+}
+
+async fn echo_text_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, msg.text().unwrap_or_default())
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_custom_distribution_function_handles_every_chat() {
+    let group = MockGroupChat::new().id(-100).build();
+    let mut bot = MockBot::new_with_distribution_function(
+        vec![
+            MockMessageText::new().text("first"),
+            MockMessageText::new().text("in group").chat(group.clone()),
+            MockMessageText::new().text("second"),
+        ],
+        Update::filter_message().endpoint(echo_text_handler),
+        chat_distribution_function,
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages.len(), 3);
+    // Updates with the same key are handled one after another, in order
+    assert_eq!(
+        responses.view().to(UserId(MockUser::ID)).texts(),
+        vec!["first", "second"]
+    );
+    assert_eq!(responses.view().to(group.id).texts(), vec!["in group"]);
+}
+
+async fn premium_bot_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let me = bot.get_me().await?;
+    let text = if me.user.is_premium {
+        "Premium bot"
+    } else {
+        "Regular bot"
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_premium_bot() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(premium_bot_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    crate::assert_text!(bot, "Regular bot");
+
+    bot.me(MockMe::new().is_premium(true));
+    bot.dispatch().await;
+    crate::assert_text!(bot, "Premium bot");
+    let responses = bot.get_responses();
+    assert!(responses.last_sender().unwrap().is_premium);
+}
+
+async fn formatted_poll_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_poll(
+        msg.chat.id,
+        "Question",
+        vec![
+            teloxide::types::InputPollOption::new("<b>Bold</b> option")
+                .text_parse_mode(teloxide::types::ParseMode::Html),
+            teloxide::types::InputPollOption::new("Plain option"),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_poll_option_entities() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(formatted_poll_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let poll = responses.sent_messages_poll[0].message.poll().unwrap();
+    assert_eq!(poll.options[0].text, "Bold option");
+    assert_eq!(
+        poll.options[0].text_entities,
+        Some(vec![MessageEntity::bold(0, 4)])
+    );
+    assert_eq!(poll.options[1].text, "Plain option");
+    assert_eq!(poll.options[1].text_entities, None);
+}
+
+async fn force_reply_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let force_reply = teloxide::types::ForceReply::new()
+        .selective()
+        .input_field_placeholder(Some("Your answer".to_string()));
+    bot.send_message(msg.chat.id, "Answer me")
+        .reply_markup(force_reply.clone())
+        .await?;
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    bot.send_photo(msg.chat.id, photo)
+        .reply_markup(force_reply)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_selective_force_reply_is_captured() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(force_reply_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let requests = [
+        responses.sent_messages_text[0]
+            .bot_request
+            .reply_markup
+            .clone(),
+        responses.sent_messages_photo[0]
+            .bot_request
+            .reply_markup
+            .clone(),
+    ];
+    for reply_markup in requests {
+        let Some(teloxide::types::ReplyMarkup::ForceReply(force_reply)) = reply_markup else {
+            panic!("ForceReply was not captured: {reply_markup:?}");
+        };
+        assert!(force_reply.selective);
+        assert_eq!(
+            force_reply.input_field_placeholder.as_deref(),
+            Some("Your answer")
+        );
+    }
+    // Only inline keyboards are attached to the sent messages
+    assert!(responses.sent_messages[0].reply_markup().is_none());
+}
+
+async fn two_polls_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_poll(msg.chat.id, "First", vec!["a".into(), "b".into()])
+        .await?;
+    bot.send_poll(msg.chat.id, "Second", vec!["a".into(), "b".into()])
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seeded_poll_ids() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(two_polls_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    let responses = bot.get_responses();
+    let poll = responses.sent_messages_poll[0].message.poll().unwrap();
+    assert_eq!(poll.id.0, MockMessagePoll::POLL_ID);
+
+    bot.seed_poll_ids(1000);
+    bot.dispatch().await;
+    let responses = bot.get_responses();
+    let ids: Vec<_> = responses
+        .sent_messages_poll
+        .iter()
+        .map(|sent| sent.message.poll().unwrap().id.0.clone())
+        .collect();
+    assert_eq!(ids, vec!["1000", "1001"]);
+}
+
+async fn channel_post_handler(
+    bot: Bot,
+    _msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(ChatId(-100), "Post").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_linked_chats_auto_forward() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(channel_post_handler),
+    )
+    .await;
+    bot.link_chats(ChatId(-100), ChatId(-200));
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let post = responses.sent_messages_text[0].message.clone();
+    let forward = &responses.automatic_forwards[0];
+    assert_eq!(forward.chat.id, ChatId(-200));
+    assert_eq!(forward.text(), Some("Post"));
+    assert!(forward.is_automatic_forward());
+    assert_eq!(forward.sender_chat.as_ref().unwrap().id, ChatId(-100));
+    let Some(teloxide::types::MessageOrigin::Channel { message_id, .. }) = forward.forward_origin()
+    else {
+        panic!("The auto-forward must come from the channel");
+    };
+    assert_eq!(*message_id, post.id);
+    assert_ne!(forward.id, post.id);
+}
+
+async fn chat_sticker_set_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.set_chat_sticker_set(msg.chat.id, "group_stickers")
+        .await?;
+    let chat = bot.get_chat(msg.chat.id).await?;
+    bot.send_message(msg.chat.id, chat.sticker_set_name().unwrap_or("none"))
+        .await?;
+
+    bot.delete_chat_sticker_set(msg.chat.id).await?;
+    let chat = bot.get_chat(msg.chat.id).await?;
+    bot.send_message(msg.chat.id, chat.sticker_set_name().unwrap_or("none"))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_chat_reflects_sticker_set() {
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("hi")
+            .chat(MockSupergroupChat::new().build()),
+        Update::filter_message().endpoint(chat_sticker_set_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.view().texts(), vec!["group_stickers", "none"]);
+    assert_eq!(
+        responses.set_chat_sticker_sets[0].sticker_set_name,
+        "group_stickers"
+    );
+    assert_eq!(responses.deleted_chat_sticker_sets.len(), 1);
+}
+
+async fn keyboard_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "Are you sure?")
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("Yes", "confirm:yes"),
+        ]]))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raw_reply_markup() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(keyboard_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    assert!(bot.get_responses().raw_requests.is_empty());
+
+    bot.capture_raw_requests(true);
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let reply_markup = responses.last_raw_reply_markup().unwrap();
+    assert!(reply_markup.contains(r#""callback_data":"confirm:yes""#));
+    assert_eq!(responses.raw_requests.last().unwrap().method, "SendMessage");
+    // The request was still handled normally
+    crate::assert_text!(bot, "Are you sure?");
+}
+
+async fn photo_keyboard_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    bot.send_photo(msg.chat.id, photo)
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("Like", "like"),
+        ]]))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raw_reply_markup_multipart() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(photo_keyboard_handler),
+    )
+    .await;
+    bot.capture_raw_requests(true);
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let reply_markup = responses.last_raw_reply_markup().unwrap();
+    assert!(reply_markup.contains(r#""callback_data":"like""#));
+    assert_eq!(responses.sent_messages_photo.len(), 1);
+}
+
+async fn keyboard_then_chat_action_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    keyboard_handler(bot.clone(), msg.clone()).await?;
+    bot.send_chat_action(msg.chat.id, teloxide::types::ChatAction::Typing)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raw_reply_markup_after_chat_action() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(keyboard_then_chat_action_handler),
+    )
+    .await;
+    bot.capture_raw_requests(true);
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.raw_requests.last().unwrap().method,
+        "SendChatAction"
+    );
+    let reply_markup = responses.last_raw_reply_markup().unwrap();
+    assert!(reply_markup.contains(r#""callback_data":"confirm:yes""#));
+}
+
+async fn forward_to_topic_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let topic = teloxide::types::ThreadId(MessageId(7));
+    bot.forward_message(ChatId(-100), msg.chat.id, msg.id)
+        .message_thread_id(topic)
+        .await?;
+    bot.copy_message(ChatId(-100), msg.chat.id, msg.id)
+        .message_thread_id(topic)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forward_and_copy_into_topic() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(forward_to_topic_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let topic = Some(teloxide::types::ThreadId(MessageId(7)));
+    let forwarded = &responses.forwarded_messages[0].message;
+    assert_eq!(forwarded.thread_id, topic);
+    assert!(forwarded.is_topic_message);
+    let copied = responses.sent_messages.last().unwrap();
+    assert_eq!(copied.id, responses.copied_messages[0].message_id);
+    assert_eq!(copied.thread_id, topic);
+    assert!(copied.is_topic_message);
+}
+
+#[tokio::test]
+async fn test_clear_responses() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema()).await;
+
+    bot.dispatch().await;
+    assert!(!bot.get_responses().sent_messages.is_empty());
+    let stored = bot.stored_message_count();
+
+    bot.clear_responses();
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages.is_empty());
+    assert!(responses.sent_messages_text.is_empty());
+    assert!(bot.take_request_log().is_empty());
+    assert_eq!(bot.stored_message_count(), stored);
+}
+
+async fn business_bot_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let text = match &msg.sender_business_bot {
+        Some(business_bot) => format!("Sent by {}", business_bot.first_name),
+        None => "Sent by the owner".to_string(),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_business_message_sender_business_bot() {
+    let business_bot = MockUser::new()
+        .id(UserId(4242))
+        .is_bot(true)
+        .first_name("Helper")
+        .build();
+    let message = MockMessageText::new()
+        .text("Hello")
+        .business_connection_id(teloxide::types::BusinessConnectionId(
+            "connection_id".to_owned(),
+        ))
+        .build();
+    let mut bot = MockBot::new(
+        MockBusinessMessage::new(message).sender_business_bot(business_bot),
+        Update::filter_business_message().endpoint(business_bot_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "Sent by Helper");
+}
+
+async fn poll_limits_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let (question, options) = match msg.text() {
+        Some("long question") => ("?".repeat(301), vec!["a".into(), "b".into()]),
+        Some("few options") => ("Question".to_string(), vec!["a".into()]),
+        // The markup doesn't count towards the limit
+        Some("formatted option") => (
+            "Question".to_string(),
+            vec![
+                "a".into(),
+                teloxide::types::InputPollOption::new(format!("<b>{}</b>", "b".repeat(100)))
+                    .text_parse_mode(teloxide::types::ParseMode::Html),
+            ],
+        ),
+        _ => ("?".repeat(300), vec!["a".into(), "b".repeat(100).into()]),
+    };
+    let text = match bot.send_poll(msg.chat.id, question, options).await {
+        Ok(_) => "Sent".to_string(),
+        Err(teloxide::RequestError::Api(error)) => format!("{error:?}"),
+        Err(error) => return Err(error.into()),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_poll_question_too_long() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("long question"),
+        Update::filter_message().endpoint(poll_limits_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "PollQuestionLengthTooLong");
+    assert!(bot.get_responses().sent_messages_poll.is_empty());
+}
+
+#[tokio::test]
+async fn test_poll_too_few_options() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("few options"),
+        Update::filter_message().endpoint(poll_limits_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "PollMustHaveMoreOptions");
+    assert!(bot.get_responses().sent_messages_poll.is_empty());
+}
+
+#[tokio::test]
+async fn test_poll_within_limits() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("valid"),
+        Update::filter_message().endpoint(poll_limits_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "Sent");
+    assert_eq!(bot.get_responses().sent_messages_poll.len(), 1);
+}
+
+#[tokio::test]
+async fn test_poll_formatted_option_within_limits() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("formatted option"),
+        Update::filter_message().endpoint(poll_limits_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "Sent");
+    let responses = bot.get_responses();
+    let poll = responses.sent_messages_poll[0].message.poll().unwrap();
+    assert_eq!(poll.options[1].text, "b".repeat(100));
+}
+
+async fn protected_media_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let file = || InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    bot.send_photo(msg.chat.id, file())
+        .protect_content(true)
+        .await?;
+    bot.send_video(msg.chat.id, file())
+        .protect_content(true)
+        .await?;
+    bot.send_document(msg.chat.id, file())
+        .protect_content(true)
+        .await?;
+    bot.send_photo(msg.chat.id, file()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_protected_media() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(protected_media_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages_photo[0]
+        .message
+        .has_protected_content());
+    assert!(responses.sent_messages_video[0]
+        .message
+        .has_protected_content());
+    assert!(responses.sent_messages_document[0]
+        .message
+        .has_protected_content());
+    assert!(!responses.sent_messages_photo[1]
+        .message
+        .has_protected_content());
+}
+
+#[tokio::test]
+async fn test_responses_is_empty() {
+    let mut bot = MockBot::new(MockMessageText::new().text("not a command"), get_schema()).await;
+
+    bot.dispatch().await;
+    let responses = bot.get_responses();
+    assert!(responses.is_empty());
+    assert_eq!(responses.total_calls(), 0);
+
+    bot.update(MockMessageText::new().text("/echo hi"));
+    bot.dispatch().await;
+    let responses = bot.get_responses();
+    assert!(!responses.is_empty());
+    assert_eq!(responses.total_calls(), 1);
+}
+
+async fn copy_effect_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.copy_message(msg.chat.id, msg.chat.id, msg.id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_message_drops_effect() {
+    let message = MockMessageText::new()
+        .text("Party")
+        .effect_id(teloxide::types::EffectId::from(
+            "5046509860389126442".to_string(),
+        ))
+        .is_from_offline(true);
+    let mut bot = MockBot::new(
+        message,
+        Update::filter_message().endpoint(copy_effect_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let copy = responses.sent_messages.last().unwrap();
+    assert_eq!(copy.id, responses.copied_messages[0].message_id);
+    assert_eq!(copy.text(), Some("Party"));
+    assert_eq!(copy.effect_id(), None);
+    let teloxide::types::MessageKind::Common(common) = &copy.kind else {
+        panic!("The copy must be a common message");
+    };
+    assert!(!common.is_from_offline);
+}
+
+#[tokio::test]
+async fn test_update_with_raw_update() {
+    // An update as it could be captured from the real Bot API
+    let update: Update = serde_json::from_str(
+        r#"{
+            "update_id": 1,
+            "message": {
+                "message_id": 10,
+                "date": 1700000000,
+                "chat": {"id": 987654, "type": "private", "first_name": "Replayed"},
+                "from": {"id": 987654, "is_bot": false, "first_name": "Replayed"},
+                "text": "/echo replayed"
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo first"), get_schema()).await;
+
+    bot.update(update);
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages.last().unwrap();
+    assert_eq!(sent.text(), Some("/echo replayed"));
+    assert_eq!(sent.chat.id, ChatId(987654));
+}
+
+async fn button_kinds_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let url = url::Url::parse("https://example.com/app").unwrap();
+    bot.send_message(msg.chat.id, "Pick one")
+        .reply_markup(InlineKeyboardMarkup::new(vec![
+            vec![InlineKeyboardButton::web_app(
+                "Open app",
+                teloxide::types::WebAppInfo { url: url.clone() },
+            )],
+            vec![InlineKeyboardButton::login(
+                "Log in",
+                teloxide::types::LoginUrl {
+                    url,
+                    forward_text: None,
+                    bot_username: None,
+                    request_write_access: Some(true),
+                },
+            )],
+            vec![InlineKeyboardButton::switch_inline_query("Share", "query")],
+        ]))
+        .await?;
+    bot.send_message(msg.chat.id, "No buttons").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inline_button_kinds_are_kept() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(button_kinds_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let button = responses.find_web_app_button().unwrap();
+    assert_eq!(button.text, "Open app");
+    let teloxide::types::InlineKeyboardButtonKind::WebApp(info) = &button.kind else {
+        unreachable!();
+    };
+    assert_eq!(info.url.as_str(), "https://example.com/app");
+
+    let keyboard = &responses.sent_messages[0]
+        .reply_markup()
+        .unwrap()
+        .inline_keyboard;
+    assert!(matches!(
+        &keyboard[1][0].kind,
+        teloxide::types::InlineKeyboardButtonKind::LoginUrl(login)
+            if login.request_write_access == Some(true)
+    ));
+    assert_eq!(
+        keyboard[2][0].kind,
+        teloxide::types::InlineKeyboardButtonKind::SwitchInlineQuery("query".to_string())
+    );
+}
+
+async fn saved_messages_handler(
+    bot: Bot,
+    me: teloxide::types::Me,
+    _msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(me.user.id, "Note to self").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_to_bot_own_id() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(saved_messages_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages.last().unwrap();
+    assert_eq!(sent.text(), Some("Note to self"));
+    assert_eq!(sent.chat.id, ChatId(MockMe::ID as i64));
+    assert!(sent.chat.is_private());
+}
+
+#[tokio::test]
+async fn test_mock_bot_debug() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema()).await;
+    bot.dependencies(deps![InMemStorage::<State>::new()]);
+
+    bot.dispatch().await;
+
+    let debug = format!("{bot:?}");
+    let port = bot.api_url().port().unwrap();
+    assert!(debug.contains(&format!("port: {port}")));
+    assert!(debug.contains("pending_updates: 1"));
+    assert_eq!(debug.matches("InMemStorage<").count(), 1);
+    assert!(debug.contains("sent_messages: 1"));
+}
+
+async fn placeholder_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let keyboard = teloxide::types::KeyboardMarkup::new(vec![vec![
+        teloxide::types::KeyboardButton::new("Yes"),
+        teloxide::types::KeyboardButton::new("No"),
+    ]])
+    .input_field_placeholder("Yes or no?");
+    bot.send_message(msg.chat.id, "Are you sure?")
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_input_field_placeholder() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(placeholder_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    assert_eq!(
+        bot.get_responses().last_input_field_placeholder(),
+        Some("Yes or no?")
+    );
+
+    bot.update(MockMessageText::new().text("/echo hi"));
+    bot.set_handler(get_schema());
+    bot.dispatch().await;
+
+    assert_eq!(bot.get_responses().last_input_field_placeholder(), None);
+}
+
+async fn channel_username_handler(
+    bot: Bot,
+    _msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(
+        teloxide::types::Recipient::ChannelUsername("@teremock_news".to_string()),
+        "New release!",
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_message_link() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(channel_username_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages.last().unwrap();
+    assert!(sent.chat.is_channel());
+    assert_eq!(
+        responses.message_link(sent.id.0),
+        Some(format!("https://t.me/teremock_news/{}", sent.id.0))
+    );
+    assert_eq!(responses.message_link(sent.id.0 + 100), None);
+}
+
+#[tokio::test]
+async fn test_message_link_private_chat() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema()).await;
+    bot.register_chat(MockPrivateChat::new().username("alice").build());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages.last().unwrap();
+    assert_eq!(sent.chat.username(), Some("alice"));
+    // Messages in private chats have no public link, even if the user has a username
+    assert_eq!(responses.message_link(sent.id.0), None);
+}
+
+async fn overlapping_entities_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // Code nested in bold is fine
+    bot.send_message(msg.chat.id, "bold and code")
+        .entities(vec![MessageEntity::bold(0, 13), MessageEntity::code(9, 4)])
+        .await?;
+    // Bold and code that cross each other are not
+    bot.send_message(msg.chat.id, "bold and code")
+        .entities(vec![MessageEntity::bold(0, 10), MessageEntity::code(5, 8)])
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_message_overlapping_entities() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(overlapping_entities_handler),
+    )
+    .await;
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_text.len(), 1);
+    assert_eq!(
+        responses.sent_messages_text[0]
+            .message
+            .entities()
+            .unwrap()
+            .len(),
+        2
+    );
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("CantParseEntities"));
+}
+
+async fn closed_poll_handler(
+    bot: Bot,
+    poll: teloxide::types::Poll,
+    poll_message_id: MessageId,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let chat_id = ChatId(MockPrivateChat::ID);
+    if poll.is_closed {
+        bot.send_message(chat_id, "Poll closed").await?;
+        bot.forward_message(chat_id, chat_id, poll_message_id)
+            .await?;
+    } else {
+        bot.send_message(chat_id, "Poll updated").await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_closed_poll_update() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/poll"), get_schema()).await;
+    bot.seed_poll_ids(7);
+    bot.dispatch().await;
+    let poll_message = bot
+        .get_responses()
+        .sent_messages_poll
+        .pop()
+        .unwrap()
+        .message;
+    assert!(!poll_message.poll().unwrap().is_closed);
+
+    bot.update(
+        MockUpdatePoll::new()
+            .poll_id(teloxide::types::PollId("7".to_string()))
+            .is_closed(true),
+    );
+    bot.dependencies(deps![poll_message.id]);
+    bot.set_handler(Update::filter_poll().endpoint(closed_poll_handler));
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages[0].text(), Some("Poll closed"));
+    // The stored poll message was closed too
+    let forwarded = &responses.forwarded_messages[0].message;
+    assert!(forwarded.poll().unwrap().is_closed);
+}
+
+#[tokio::test]
+async fn test_latency() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema()).await;
+
+    bot.latency(std::time::Duration::from_millis(300));
+    let start = std::time::Instant::now();
+    bot.dispatch().await;
+    assert!(start.elapsed() >= std::time::Duration::from_millis(300));
+    crate::assert_text!(bot, "/echo hi");
+}
+
+async fn copy_captioned_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    let sent = bot
+        .send_photo(msg.chat.id, photo)
+        .caption("bold caption")
+        .caption_entities(vec![MessageEntity::bold(0, 4)])
+        .await?;
+    bot.copy_message(msg.chat.id, msg.chat.id, sent.id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_message_keeps_caption_entities() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(copy_captioned_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let copy = responses.sent_messages.last().unwrap();
+    assert_eq!(copy.caption(), Some("bold caption"));
+    assert_eq!(
+        copy.caption_entities(),
+        Some(&[MessageEntity::bold(0, 4)][..])
+    );
+}
+
+async fn send_and_delete_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "One").await?;
+    bot.send_message(msg.chat.id, "Two").await?;
+    let third = bot.send_message(msg.chat.id, "Three").await?;
+    bot.delete_message(msg.chat.id, third.id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_call_count() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(send_and_delete_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.call_count("/SendMessage"), 3);
+    assert_eq!(responses.call_count("/DeleteMessage"), 1);
+    assert_eq!(responses.call_count("DeleteMessage"), 1);
+    assert_eq!(responses.call_count("/SendPhoto"), 0);
+}
+
+#[tokio::test]
+async fn test_summary_counts() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(send_and_delete_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let summary = bot.get_responses().summary();
+    assert!(summary.starts_with("sent_messages: 3"));
+    assert!(summary.ends_with("\ndeleted_messages: 1"));
+}
+
+async fn download_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let document = InputFile::memory("somedata".to_string()).file_name("test.txt");
+    let document_message = bot.send_document(msg.chat.id, document).await?;
+    let file = bot
+        .get_file(document_message.document().unwrap().file.id.clone())
+        .await?;
+    let mut dest = Vec::new();
+    bot.download_file(&file.path, &mut dest).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_call_count_skips_file_downloads() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(download_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.endpoint_calls,
+        vec!["GetMe", "SendDocument", "GetFile"]
+    );
+    // The download is still in the request log
+    assert_eq!(bot.take_request_log().len(), 4);
+}
+
+async fn inline_business_edit_handler(
+    bot: Bot,
+    _msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.edit_message_text_inline("inline_42", "Edited")
+        .business_connection_id(teloxide::types::BusinessConnectionId(
+            "business_1".to_string(),
+        ))
+        .await?;
+    bot.edit_message_reply_markup_inline("inline_43").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inline_message_edit_business_connection_id() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(inline_business_edit_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let edits = &responses.inline_message_edits;
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0].method, "EditMessageText");
+    assert_eq!(edits[0].inline_message_id, "inline_42");
+    assert_eq!(
+        edits[0].business_connection_id,
+        Some(teloxide::types::BusinessConnectionId(
+            "business_1".to_string()
+        ))
+    );
+    assert_eq!(edits[1].method, "EditMessageReplyMarkup");
+    assert_eq!(edits[1].business_connection_id, None);
+}
+
+async fn group_announcement_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "Announced").await?;
+    bot.send_message(ChatId(-1001234), "Big news!").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assert_sent_to() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(group_announcement_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    bot.assert_sent_to(ChatId(-1001234), "Big news!");
+}
+
+#[tokio::test]
+#[should_panic(expected = "in chat -1001234: \"Big news!\"")]
+async fn test_assert_sent_to_wrong_chat() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(group_announcement_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    bot.assert_sent_to(ChatId(-1009999), "Big news!");
+}
+
+async fn resend_sticker_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let sticker = msg.sticker().unwrap();
+    bot.send_sticker(msg.chat.id, InputFile::file_id(sticker.file.id.clone()))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_sticker_keeps_needs_repainting() {
+    let premium_animation = teloxide::types::FileMeta {
+        id: "premium_animation".into(),
+        unique_id: "premium_unique".into(),
+        size: 100,
+    };
+    let mut bot = MockBot::new(
+        MockMessageSticker::new()
+            .file_id("known_sticker")
+            .kind(teloxide::types::StickerKind::Regular {
+                premium_animation: Some(premium_animation.clone()),
+            })
+            .needs_repainting(true)
+            .emoji("🙂"),
+        Update::filter_message().endpoint(resend_sticker_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages_sticker[0]
+        .message
+        .sticker()
+        .unwrap();
+    assert_eq!(sent.file.id.0, "known_sticker");
+    assert!(sent.needs_repainting);
+    assert_eq!(
+        sent.kind,
+        teloxide::types::StickerKind::Regular {
+            premium_animation: Some(premium_animation)
+        }
+    );
+    assert_eq!(sent.emoji.as_deref(), Some("🙂"));
+}
+
+async fn edit_caption_above_media_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+    let sent = bot
+        .send_photo(msg.chat.id, photo)
+        .caption("above")
+        .show_caption_above_media(true)
+        .await?;
+    bot.edit_message_caption(msg.chat.id, sent.id)
+        .caption("still above")
+        .await?;
+    bot.edit_message_caption(msg.chat.id, sent.id)
+        .caption("below now")
+        .show_caption_above_media(false)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_edit_caption_keeps_show_caption_above_media() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(edit_caption_above_media_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let show_above = |message: &Message| match &message.kind {
+        teloxide::types::MessageKind::Common(common) => match &common.media_kind {
+            teloxide::types::MediaKind::Photo(photo) => photo.show_caption_above_media,
+            _ => panic!("Not a photo"),
+        },
+        _ => panic!("Not a common message"),
+    };
+    assert!(show_above(&responses.sent_messages_photo[0].message));
+    let edits = &responses.edited_messages_caption;
+    assert_eq!(edits[0].message.caption(), Some("still above"));
+    assert!(show_above(&edits[0].message));
+    assert_eq!(edits[1].message.caption(), Some("below now"));
+    assert!(!show_above(&edits[1].message));
+}
+
+#[tokio::test]
+async fn test_server_pool() {
+    let pool = ServerPool::warm(2).await;
+    assert_eq!(pool.available(), 2);
+
+    let mut first = pool
+        .bot(MockMessageText::new().text("/echo first"), get_schema())
+        .await;
+    let mut second = pool
+        .bot(MockMessageText::new().text("/echo second"), get_schema())
+        .await;
+    assert_eq!(pool.available(), 0);
+    assert_ne!(first.api_url(), second.api_url());
+
+    first.dispatch().await;
+    second.dispatch().await;
+    crate::assert_text!(first, "/echo first");
+    crate::assert_text!(second, "/echo second");
+
+    // An empty pool starts a new server
+    let mut third = pool
+        .bot(MockMessageText::new().text("/echo third"), get_schema())
+        .await;
+    third.dispatch().await;
+    crate::assert_text!(third, "/echo third");
+}
+
+async fn offline_business_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let is_from_offline = match &msg.kind {
+        teloxide::types::MessageKind::Common(common) => common.is_from_offline,
+        _ => false,
+    };
+    bot.send_message(msg.chat.id, format!("Offline: {is_from_offline}"))
+        .await?;
+    // The business message is stored, so it can be copied
+    bot.copy_message(msg.chat.id, msg.chat.id, msg.id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_offline_business_message() {
+    let message = MockMessageText::new()
+        .text("Away message")
+        .business_connection_id(teloxide::types::BusinessConnectionId(
+            "business_1".to_string(),
+        ))
+        .build();
+    let mut bot = MockBot::new(
+        MockBusinessMessage::new(message).is_from_offline(true),
+        Update::filter_business_message().endpoint(offline_business_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages[0].text(), Some("Offline: true"));
+    let copy = &responses.sent_messages[1];
+    assert_eq!(copy.text(), Some("Away message"));
+    match &copy.kind {
+        teloxide::types::MessageKind::Common(common) => assert!(!common.is_from_offline),
+        _ => panic!("Not a common message"),
+    }
+}
+
+async fn reply_to_zero_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, "Reply")
+        .reply_parameters(ReplyParameters::new(MessageId(0)))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reply_parameters_non_positive_message_id() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(reply_to_zero_handler),
+    )
+    .await;
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    assert!(bot.get_responses().sent_messages.is_empty());
+    let request_log = bot.take_request_log();
+    let send_log = request_log
+        .iter()
+        .find(|line| line.starts_with("SendMessage"))
+        .unwrap();
+    assert!(send_log.starts_with("SendMessage 400"));
+    assert!(send_log.contains("message_id in reply_parameters must be positive, got 0"));
+    assert_eq!(error_handler.errors().len(), 1);
+}
+
+async fn custom_title_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let title = msg.author_signature().unwrap_or("member");
+    bot.send_message(msg.chat.id, format!("Hello, {title}!"))
+        .reply_to(msg.id)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_supergroup_admin_custom_title() {
+    let group = MockSupergroupChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("Announcement")
+            .chat(group.clone())
+            .sender_chat(group)
+            .author_signature("Chief"),
+        Update::filter_message().endpoint(custom_title_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "Hello, Chief!");
+    let responses = bot.get_responses();
+    let reply_to = responses.sent_messages[0].reply_to_message().unwrap();
+    assert_eq!(reply_to.author_signature(), Some("Chief"));
+}
+
+async fn failing_handler(
+    _bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    match msg.text() {
+        Some("fail") => Err("The handler failed".into()),
+        _ => Ok(()),
+    }
+}
+
+#[tokio::test]
+async fn test_dispatch_once() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema()).await;
+
+    assert!(bot.dispatch_once().await.is_ok());
+    crate::assert_text!(bot, "/echo hi");
+
+    bot.update(MockMessageText::new().text("fail"));
+    bot.set_handler(Update::filter_message().endpoint(failing_handler));
+    let error = bot.dispatch_once().await.unwrap_err();
+    assert_eq!(error.to_string(), "The handler failed");
+}
+
+async fn dice_effect_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_dice(msg.chat.id)
+        .message_effect_id(teloxide::types::EffectId::from(
+            "5104841245755180586".to_string(),
+        ))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_dice_effect_id() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(dice_effect_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let dice = &responses.sent_messages_dice[0];
+    assert_eq!(
+        dice.bot_request.message_effect_id,
+        Some(teloxide::types::EffectId::from(
+            "5104841245755180586".to_string()
+        ))
+    );
+    assert_eq!(
+        responses.effect_id(dice.message.id.0),
+        Some(&teloxide::types::EffectId::from(
+            "5104841245755180586".to_string()
+        ))
+    );
+}
+
+async fn quiz_explanation_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_poll(
+        msg.chat.id,
+        "2 + 2?",
+        vec!["4".to_string().into(), "5".to_string().into()],
+    )
+    .type_(PollType::Quiz)
+    .correct_option_id(0)
+    .explanation("<b>Four</b>, obviously")
+    .explanation_parse_mode(teloxide::types::ParseMode::Html)
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_quiz_explanation() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(quiz_explanation_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let poll = responses.sent_messages_poll[0].message.poll().unwrap();
+    assert_eq!(poll.explanation.as_deref(), Some("Four, obviously"));
+    assert_eq!(
+        poll.explanation_entities,
+        Some(vec![MessageEntity::bold(0, 4)])
+    );
+}
+
+async fn ban_and_check_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let user_id = msg.from.as_ref().unwrap().id;
+    let before = bot.get_chat_member(msg.chat.id, user_id).await?;
+    bot.send_message(
+        msg.chat.id,
+        format!("Banned before: {}", before.is_banned()),
+    )
+    .await?;
+
+    let until_date = Utc::now() + chrono::Duration::days(1);
+    bot.ban_chat_member(msg.chat.id, user_id)
+        .until_date(until_date)
+        .await?;
+    let after = bot.get_chat_member(msg.chat.id, user_id).await?;
+    match after.kind {
+        teloxide::types::ChatMemberKind::Banned(banned) => {
+            let teloxide::types::UntilDate::Date(date) = banned.until_date else {
+                panic!("Banned forever");
+            };
+            assert_eq!(date.timestamp(), until_date.timestamp());
+            bot.send_message(msg.chat.id, format!("Banned: {}", after.user.first_name))
+                .await?;
+        }
+        _ => panic!("Not banned"),
+    }
+
+    bot.unban_chat_member(msg.chat.id, user_id).await?;
+    let unbanned = bot.get_chat_member(msg.chat.id, user_id).await?;
+    bot.send_message(msg.chat.id, format!("Left: {}", unbanned.kind.is_left()))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_chat_member_banned() {
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("hi")
+            .chat(MockSupergroupChat::new().build()),
+        Update::filter_message().endpoint(ban_and_check_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.sent_messages[0].text(),
+        Some("Banned before: false")
+    );
+    assert_eq!(
+        responses.sent_messages[1].text(),
+        Some(format!("Banned: {}", MockUser::FIRST_NAME).as_str())
+    );
+    assert_eq!(responses.sent_messages[2].text(), Some("Left: true"));
+    assert_eq!(responses.banned_chat_members.len(), 1);
+}
+
+async fn edit_text_then_markup_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let sent = bot.send_message(msg.chat.id, "first").await?;
+    bot.edit_message_text(msg.chat.id, sent.id, "second")
+        .await?;
+    bot.edit_message_reply_markup(msg.chat.id, sent.id)
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("test", "test"),
+        ]]))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_total_edits() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(edit_text_then_markup_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.total_edits(), 2);
+    assert_eq!(responses.total_calls(), 3);
+}
+
+async fn persistent_keyboard_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let keyboard =
+        teloxide::types::KeyboardMarkup::new(vec![vec![teloxide::types::KeyboardButton::new(
+            "Menu",
+        )]])
+        .persistent();
+    bot.send_message(msg.chat.id, "Pick an option")
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_persistent_keyboard() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(persistent_keyboard_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    assert_eq!(
+        bot.get_responses().last_keyboard_is_persistent(),
+        Some(true)
+    );
+
+    bot.update(MockMessageText::new().text("/echo hi"));
+    bot.set_handler(get_schema());
+    bot.dispatch().await;
+
+    assert_eq!(bot.get_responses().last_keyboard_is_persistent(), None);
+}
+
+async fn invalid_media_group_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("photo.jpg");
+    let empty_video = InputFile::file_id(teloxide::types::FileId(String::new()));
+    let result = bot
+        .send_media_group(
+            msg.chat.id,
+            vec![
+                InputMedia::Photo(InputMediaPhoto::new(photo)),
+                InputMedia::Video(InputMediaVideo::new(empty_video)),
+            ],
+        )
+        .await;
+    if let Err(err) = result {
+        bot.send_message(msg.chat.id, err.to_string()).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_media_group_with_invalid_item() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(invalid_media_group_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_media_group.is_empty());
+    assert!(responses.sent_messages_photo.is_empty());
+    assert_eq!(responses.sent_messages.len(), 1);
+    assert!(responses.sent_messages[0]
+        .text()
+        .unwrap()
+        .contains("failed to send message #2 of the media group: the media is empty"));
+}
+
+async fn slow_mode_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let chat = bot.get_chat(msg.chat.id).await?;
+    let delay = chat
+        .slow_mode_delay()
+        .map(|delay| delay.seconds().to_string())
+        .unwrap_or("none".to_string());
+    bot.send_message(msg.chat.id, delay).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_chat_reflects_slow_mode() {
+    let chat = MockSupergroupChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi").chat(chat.clone()),
+        Update::filter_message().endpoint(slow_mode_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    crate::assert_text!(bot, "none");
+
+    bot.slow_mode(chat.id, teloxide::types::Seconds::from_seconds(30));
+    bot.dispatch().await;
+    crate::assert_text!(bot, "30");
+}
+
+async fn registered_chat_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let chat = bot.get_chat(ChatId(-1001)).await?;
+    let kind = if chat.is_channel() {
+        "channel"
+    } else {
+        "other"
+    };
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "{kind} {} @{} linked to {}",
+            chat.title().unwrap_or_default(),
+            chat.username().unwrap_or_default(),
+            chat.linked_chat_id().unwrap_or_default()
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_chat_registered_channel() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(registered_chat_handler),
+    )
+    .await;
+    bot.register_chat(
+        MockChannelChat::new()
+            .id(-1001)
+            .title("News")
+            .username("news")
+            .build(),
+    );
+    bot.link_chats(ChatId(-1001), ChatId(-1002));
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "channel News @news linked to -1002");
+}
+
+async fn formatted_quote_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let mut reply_parameters = ReplyParameters::new(msg.id);
+    reply_parameters.quote = Some("hello world".to_string());
+    reply_parameters.quote_entities = Some(vec![MessageEntity::bold(0, 5)]);
+    bot.send_message(msg.chat.id, "Quoted")
+        .reply_parameters(reply_parameters)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_quote() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hello world"),
+        Update::filter_message().endpoint(formatted_quote_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    assert_eq!(
+        bot.get_responses().last_quote(),
+        Some(("hello world".to_string(), vec![MessageEntity::bold(0, 5)]))
+    );
+}
+
+async fn broken_quote_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let mut reply_parameters = ReplyParameters::new(msg.id);
+    reply_parameters.quote = Some("<b>hello</i>".to_string());
+    reply_parameters.quote_parse_mode = Some("HTML".to_string());
+    let result = bot
+        .send_message(msg.chat.id, "Quoted")
+        .reply_parameters(reply_parameters)
+        .await;
+    if let Err(err) = result {
+        bot.send_message(msg.chat.id, err.to_string()).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_rejects_unparsable_quote() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hello world"),
+        Update::filter_message().endpoint(broken_quote_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages.len(), 1);
+    assert!(responses.sent_messages[0]
+        .text()
+        .unwrap()
+        .contains("can't parse entities"));
+    assert_eq!(responses.last_quote(), None);
+}
+
+async fn unimplemented_endpoint_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    // The error is swallowed, like a careless bot would do it
+    let _ = bot.set_chat_title(msg.chat.id, "New title").await;
+    bot.send_message(msg.chat.id, "Done").await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic(expected = "The bot called unimplemented endpoints: SetChatTitle")]
+async fn test_fail_on_unimplemented() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(unimplemented_endpoint_handler),
+    )
+    .await;
+    bot.fail_on_unimplemented();
+
+    bot.dispatch().await;
+
+    bot.verify();
+}
+
+#[tokio::test]
+async fn test_verify_does_not_poison_the_state() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(unimplemented_endpoint_handler),
+    )
+    .await;
+    bot.fail_on_unimplemented();
+    bot.dispatch().await;
+
+    let verified = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| bot.verify()));
+
+    assert!(verified.is_err());
+    bot.strict(false);
+    assert_eq!(bot.get_responses().sent_messages.len(), 1);
+}
+
+async fn edit_caption_date_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let photo = InputFile::memory("somedata".to_string()).file_name("photo.jpg");
+    let sent = bot.send_photo(msg.chat.id, photo).caption("old").await?;
+    bot.edit_message_caption(msg.chat.id, sent.id)
+        .caption("new")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_edit_caption_sets_edit_date() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(edit_caption_date_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages_photo[0]
+        .message
+        .edit_date()
+        .is_none());
+    let edited = &responses.edited_messages_caption[0].message;
+    assert_eq!(edited.caption(), Some("new"));
+    let edit_date = edited.edit_date().expect("The edit date wasn't set");
+    assert!((Utc::now() - *edit_date).num_seconds() < 60);
+}
+
+#[tokio::test]
+async fn test_set_clock_tz() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema()).await;
+    let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T09:30:00+03:00").unwrap();
+    bot.set_clock_tz(now);
+
+    bot.dispatch().await;
+
+    let date = bot.get_responses().sent_messages[0].date;
+    assert_eq!(date, now);
+    assert_eq!(date.timestamp(), 1714545000);
+    assert_eq!(
+        date.with_timezone(now.offset()).to_rfc3339(),
+        "2024-05-01T09:30:00+03:00"
+    );
+}
+
+async fn ban_until_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let user_id = msg.from.as_ref().unwrap().id;
+    let until_date = chrono::DateTime::parse_from_rfc3339("2024-05-02T09:30:00+03:00")?;
+    bot.ban_chat_member(msg.chat.id, user_id)
+        .until_date(until_date.with_timezone(&Utc))
+        .await?;
+    let member = bot.get_chat_member(msg.chat.id, user_id).await?;
+    if let teloxide::types::ChatMemberKind::Banned(banned) = member.kind {
+        bot.send_message(msg.chat.id, format!("{:?}", banned.until_date))
+            .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ban_chat_member_uses_the_clock() {
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("hi")
+            .chat(MockSupergroupChat::new().build()),
+        Update::filter_message().endpoint(ban_until_handler),
+    )
+    .await;
+    let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T09:30:00+03:00").unwrap();
+    bot.set_clock_tz(now);
+
+    bot.dispatch().await;
+
+    // The date is in the past for the real clock, but in the future for the fixed one
+    let text = bot.get_responses().sent_messages[0]
+        .text()
+        .unwrap()
+        .to_owned();
+    assert!(text.starts_with("Date("), "{text}");
+}
+
+#[tokio::test]
+async fn test_forward_channel_post_origin_message_id() {
+    let channel = MockChannelChat::new().build();
+    let post = MockMessageText::new()
+        .text("Post")
+        .id(42)
+        .chat(channel.clone())
+        .build();
+    let mut bot = MockBot::new(
+        MockChannelPost::new(post),
+        Update::filter_channel_post().endpoint(forward_channel_post_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let forwarded = &responses.forwarded_messages[0].message;
+    assert_ne!(forwarded.id, MessageId(42));
+    match forwarded.forward_origin() {
+        Some(teloxide::types::MessageOrigin::Channel {
+            chat, message_id, ..
+        }) => {
+            assert_eq!(chat.id, channel.id);
+            assert_eq!(*message_id, MessageId(42));
+        }
+        origin => panic!("Expected a channel origin, got {origin:?}"),
+    }
+}
+
+async fn deep_link_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let payload = msg
+        .text()
+        .and_then(|text| text.strip_prefix("/start "))
+        .unwrap_or("no payload");
+    bot.send_message(msg.chat.id, format!("Payload: {payload}"))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deep_link() {
+    let deep_link = MockMessageText::deep_link("ref_42");
+    assert_eq!(deep_link.clone().build().text(), Some("/start ref_42"));
+    let mut bot = MockBot::new(
+        deep_link,
+        Update::filter_message().endpoint(deep_link_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "Payload: ref_42");
+}
+
+#[tokio::test]
+async fn test_send_custom_emoji_sticker() {
+    // The Bot API can't set the custom emoji id, it comes with the sticker sent by its file id
+    let custom_emoji_id = teloxide::types::CustomEmojiId("5368324170671202286".to_string());
+    let mut bot = MockBot::new(
+        MockMessageSticker::new()
+            .file_id("custom_emoji_sticker")
+            .kind(teloxide::types::StickerKind::CustomEmoji {
+                custom_emoji_id: custom_emoji_id.clone(),
+            }),
+        Update::filter_message().endpoint(resend_sticker_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent = responses.sent_messages_sticker[0]
+        .message
+        .sticker()
+        .unwrap();
+    assert_eq!(sent.custom_emoji_id(), Some(&custom_emoji_id));
+}
+
+async fn usd_invoice_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    bot.send_invoice(
+        msg.chat.id,
+        "Coffee",
+        "A cup of coffee with a cookie",
+        "coffee_payload",
+        "USD",
+        vec![
+            LabeledPrice {
+                label: "Coffee".into(),
+                amount: 350,
+            },
+            LabeledPrice {
+                label: "Cookie".into(),
+                amount: 125,
+            },
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_invoice_total() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(usd_invoice_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    assert_eq!(
+        bot.get_responses().last_invoice_total(),
+        Some(("USD".to_string(), 475))
+    );
+}
+
+#[tokio::test]
+async fn test_chat_actions_typed() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/chataction"), get_schema()).await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_chat_actions[0].action, "typing");
+    assert_eq!(responses.chat_actions_typed(), vec![ChatAction::Typing]);
+}
+
+async fn two_threads_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let first = teloxide::types::ThreadId(MessageId(7));
+    let second = teloxide::types::ThreadId(MessageId(8));
+    bot.send_message(msg.chat.id, "First topic")
+        .message_thread_id(first)
+        .await?;
+    bot.send_message(msg.chat.id, "Second topic")
+        .message_thread_id(second)
+        .await?;
+    bot.send_message(msg.chat.id, "No topic").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_messages_in_thread() {
+    let mut bot = MockBot::new(
+        MockMessageText::new()
+            .text("hi")
+            .chat(MockSupergroupChat::new().build()),
+        Update::filter_message().endpoint(two_threads_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let in_first = responses.messages_in_thread(teloxide::types::ThreadId(MessageId(7)));
+    assert_eq!(in_first.len(), 1);
+    assert_eq!(in_first[0].text(), Some("First topic"));
+    assert!(in_first[0].is_topic_message);
+    assert!(responses
+        .messages_in_thread(teloxide::types::ThreadId(MessageId(9)))
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_answer_callback_query_text_too_long() {
+    let mut bot = MockBot::new(MockCallbackQuery::new().data("a".repeat(201)), get_schema()).await;
+
+    bot.dispatch().await;
+
+    assert!(bot.get_responses().answered_callback_queries.is_empty());
+    let request_log = bot.take_request_log();
+    let answer_log = request_log
+        .iter()
+        .find(|line| line.starts_with("AnswerCallbackQuery"))
+        .unwrap();
+    assert!(answer_log.contains("MESSAGE_TOO_LONG"));
+
+    bot.update(MockCallbackQuery::new().data("a".repeat(200)));
+    bot.dispatch().await;
+
+    let answered = bot.get_responses().answered_callback_queries.pop().unwrap();
+    assert_eq!(answered.text.map(|text| text.len()), Some(200));
+}
+
+async fn localized_greeting_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let language = msg
+        .from
+        .as_ref()
+        .and_then(|user| user.language_code.as_deref());
+    let greeting = match language {
+        Some("fr") => "Bonjour !",
+        _ => "Hello!",
+    };
+    bot.send_message(msg.chat.id, greeting).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_user_language() {
+    let french_user = MockUser::new().language_code("fr").build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi").from(french_user),
+        Update::filter_message().endpoint(localized_greeting_handler),
+    )
+    .await;
+    assert_eq!(bot.last_user_language(), Some("fr"));
+
+    bot.dispatch().await;
+    crate::assert_text!(bot, "Bonjour !");
+
+    bot.update(MockMessageText::new().text("hi"));
+    assert_eq!(bot.last_user_language(), None);
+    bot.dispatch().await;
+    crate::assert_text!(bot, "Hello!");
+}
+
+async fn copy_caption_above_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    bot.copy_message(msg.chat.id, msg.chat.id, msg.id)
+        .show_caption_above_media(true)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_message_show_caption_above_media() {
+    let mut bot = MockBot::new(
+        MockMessagePhoto::new().caption("Look at this"),
+        Update::filter_message().endpoint(copy_caption_above_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let copy = responses.sent_messages.last().unwrap();
+    assert_eq!(copy.id, responses.copied_messages[0].message_id);
+    assert_eq!(copy.caption(), Some("Look at this"));
+    assert!(copy.show_caption_above_media());
+    assert_eq!(
+        responses.copied_messages[0]
+            .bot_request
+            .show_caption_above_media,
+        Some(true)
+    );
+}
+
+#[tokio::test]
+async fn test_inline_keyboard() {
+    let keyboard = crate::inline_keyboard(&[
+        &[("1", "one"), ("2", "two")],
+        &[("3", "three"), ("4", "four")],
+    ]);
+
+    assert_eq!(keyboard.inline_keyboard.len(), 2);
+    assert!(keyboard.inline_keyboard.iter().all(|row| row.len() == 2));
+    assert_eq!(
+        keyboard.inline_keyboard[1][0],
+        InlineKeyboardButton::callback("3", "three")
+    );
+    assert_eq!(
+        keyboard,
+        InlineKeyboardMarkup::new(vec![
+            vec![
+                InlineKeyboardButton::callback("1", "one"),
+                InlineKeyboardButton::callback("2", "two"),
+            ],
+            vec![
+                InlineKeyboardButton::callback("3", "three"),
+                InlineKeyboardButton::callback("4", "four"),
+            ],
+        ])
+    );
+}
+
+async fn react_twice_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let sent = bot.send_message(msg.chat.id, "React to me").await?;
+    for message_id in [msg.id, sent.id] {
+        bot.set_message_reaction(msg.chat.id, message_id)
+            .reaction(vec![ReactionType::Emoji {
+                emoji: "👍".to_owned(),
+            }])
+            .await?;
+    }
+    // Removing the reactions isn't counted
+    bot.set_message_reaction(msg.chat.id, sent.id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_total_reactions() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(react_twice_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.set_message_reaction.len(), 3);
+    assert_eq!(responses.total_reactions(), 2);
+}
+
+async fn edit_gone_message_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let Some(message_id) = msg
+        .text()
+        .and_then(|text| text.strip_prefix("edit "))
+        .and_then(|id| id.parse().ok())
+    else {
+        bot.send_message(msg.chat.id, "Original").await?;
+        return Ok(());
+    };
+    match bot
+        .edit_message_text(msg.chat.id, MessageId(message_id), "Edited")
+        .await
+    {
+        Err(teloxide::RequestError::Api(teloxide::ApiError::MessageToEditNotFound)) => {
+            bot.send_message(msg.chat.id, "Message is gone").await?;
+        }
+        result => {
+            result?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_stored_message() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("send"),
+        Update::filter_message().endpoint(edit_gone_message_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    let sent = bot.get_responses().sent_messages[0].clone();
+
+    let deleted = bot.delete_stored_message(sent.id);
+    assert_eq!(deleted.map(|message| message.id), Some(sent.id));
+    assert!(bot.delete_stored_message(sent.id).is_none());
+
+    bot.update(MockMessageText::new().text(format!("edit {}", sent.id.0)));
+    bot.dispatch().await;
+
+    crate::assert_text!(bot, "Message is gone");
+    assert!(bot.get_responses().edited_messages_text.is_empty());
+}
+
+async fn quote_position_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let mut reply_parameters = ReplyParameters::new(msg.id);
+    reply_parameters.quote = Some("world".to_string());
+    reply_parameters.quote_position = Some(6);
+    bot.send_message(msg.chat.id, "Quoted")
+        .reply_parameters(reply_parameters)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_quote_position() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hello world"),
+        Update::filter_message().endpoint(quote_position_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.last_quote_position(), Some(6));
+    assert_eq!(responses.last_quote(), Some(("world".to_string(), vec![])));
+}
+
+async fn get_me_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let me = bot.get_me().await?;
+    bot.send_message(msg.chat.id, serde_json::to_string(&me).unwrap())
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_me_is_stable_across_dispatches() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(get_me_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    let first = bot.get_responses().sent_messages[0]
+        .text()
+        .unwrap()
+        .to_owned();
+    bot.dispatch().await;
+    let second = bot.get_responses().sent_messages[0]
+        .text()
+        .unwrap()
+        .to_owned();
+
+    assert_eq!(first, second);
+    let me: teloxide::types::Me = serde_json::from_str(&first).unwrap();
+    assert_eq!(me, bot.me);
+}
+
+async fn formatted_question_handler(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let options = || vec!["Yes".to_string().into(), "No".to_string().into()];
+    bot.send_poll(msg.chat.id, "Do you like bold?", options())
+        .question_entities(vec![MessageEntity::bold(12, 4)])
+        .await?;
+    bot.send_poll(msg.chat.id, "Do you like <i>italic</i>?", options())
+        .question_parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_poll_question_entities() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        Update::filter_message().endpoint(formatted_question_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let bold = responses.sent_messages_poll[0].message.poll().unwrap();
+    assert_eq!(bold.question, "Do you like bold?");
+    assert_eq!(
+        bold.question_entities,
+        Some(vec![MessageEntity::bold(12, 4)])
+    );
+    let italic = responses.sent_messages_poll[1].message.poll().unwrap();
+    assert_eq!(italic.question, "Do you like italic?");
+    assert_eq!(
+        italic.question_entities,
+        Some(vec![MessageEntity::italic(12, 6)])
+    );
+}
+
+async fn silent_callback_handler(bot: Bot, call: CallbackQuery) -> ResponseResult<()> {
+    if call.data.as_deref() == Some("silent") {
+        bot.answer_callback_query(call.id).await?;
+    } else {
+        bot.answer_callback_query(call.id)
+            .text("Saved!")
+            .show_alert(true)
+            .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_callback_was_silent() {
+    let mut bot = MockBot::new(
+        MockCallbackQuery::new().data("silent"),
+        Update::filter_callback_query().endpoint(silent_callback_handler),
+    )
+    .await;
+
+    bot.dispatch().await;
+    assert!(bot.get_responses().last_callback_was_silent());
+
+    bot.update(MockCallbackQuery::new().data("alert"));
+    bot.dispatch().await;
+    assert!(!bot.get_responses().last_callback_was_silent());
+}
+
+async fn send_to_group_handler(bot: Bot, _: Message) -> ResponseResult<()> {
+    bot.send_message(ChatId(-1001234), "Hello, book club!")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sent_message_carries_registered_chat() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("Hi!"),
+        Update::filter_message().endpoint(send_to_group_handler),
+    )
+    .await;
+    bot.register_chat(
+        MockSupergroupChat::new()
+            .id(-1001234)
+            .title("Book club")
+            .username("bookclub")
+            .build(),
+    );
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let message = &responses.sent_messages.last().unwrap();
+    assert_eq!(message.chat.id, ChatId(-1001234));
+    assert_eq!(message.chat.title(), Some("Book club"));
+    assert_eq!(message.chat.username(), Some("bookclub"));
+}
+
+#[tokio::test]
+async fn test_send_media_group_with_wrongly_typed_field() {
+    let bot = MockBot::new(MockMessageText::new().text("hi"), get_schema()).await;
+
+    // Teloxide only sends well typed fields, so the request is made by hand
+    let url = format!("{}bot{}/SendMediaGroup", bot.api_url(), bot.bot.token());
+    let media = serde_json::json!([
+        {"type": "photo", "media": "first_file_id", "has_spoiler": "yes"},
+        {"type": "photo", "media": "second_file_id"},
+    ]);
+    let boundary = "teremock_boundary";
+    let body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"chat_id\"\r\n\r\n123\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"media\"\r\n\r\n{media}\r\n\
+         --{boundary}--\r\n"
+    );
+    let response = teloxide::net::client_from_env()
+        .post(url)
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("failed to send message #1 of the media group: has_spoiler is invalid"));
+    assert!(bot.get_responses().sent_media_group.is_empty());
+}
@@ -5,7 +5,10 @@
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, Data, DeriveInput, Fields, PathArguments, Type, TypeGroup};
+use syn::{
+    parse_macro_input, punctuated::Punctuated, Data, DeriveInput, Fields, FnArg, ItemFn, Meta,
+    PathArguments, Token, Type, TypeGroup,
+};
 
 #[proc_macro_derive(Changeable)]
 pub fn changeable_derive(input: TokenStream) -> TokenStream {
@@ -195,6 +198,7 @@ pub fn serialize_raw_fields_derive(input: TokenStream) -> TokenStream {
         unimplemented!();
     };
 
+    #[rustfmt::skip]
     let field_serializers = fields.iter().filter(|field| field.ident.as_ref().unwrap() != "file_name" && field.ident.as_ref().unwrap() != "file_data").map(|field| {
         let field_name = field.ident.as_ref().unwrap();
         let field_type = &field.ty;
@@ -230,11 +234,11 @@ pub fn serialize_raw_fields_derive(input: TokenStream) -> TokenStream {
             }
         } else if !is_option {
             quote! {
-                #field_name: serde_json::from_str(&fields.get(#key).unwrap_or(&String::new())).ok()?,
+                #field_name: fields.get(#key).and_then(|raw| Self::raw_field(raw))?,
             }
         } else {
             quote! {
-                #field_name: serde_json::from_str(&fields.get(#key).unwrap_or(&String::new())).ok(),
+                #field_name: fields.get(#key).and_then(|raw| Self::raw_field(raw)),
             }
         }
     });
@@ -275,3 +279,79 @@ pub fn serialize_raw_fields_derive(input: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// Turns an async function taking a `MockBot` into a test, see `teremock::test`
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args with Punctuated::<Meta, Token![,]>::parse_terminated);
+    let mut function = parse_macro_input!(input as ItemFn);
+
+    match expand_test(args, &mut function) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_test(
+    args: Punctuated<Meta, Token![,]>,
+    function: &mut ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut update = None;
+    let mut tree = None;
+    let mut runtime = true;
+    for arg in args {
+        match arg {
+            Meta::NameValue(name_value) if name_value.path.is_ident("update") => {
+                update = Some(name_value.value)
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("tree") => {
+                tree = Some(name_value.value)
+            }
+            Meta::Path(path) if path.is_ident("no_runtime") => runtime = false,
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected `update = ...`, `tree = ...` or `no_runtime`",
+                ))
+            }
+        }
+    }
+    let span = function.sig.ident.span();
+    let update = update.ok_or_else(|| syn::Error::new(span, "missing `update = ...`"))?;
+    let tree = tree.ok_or_else(|| syn::Error::new(span, "missing `tree = ...`"))?;
+
+    if function.sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            function.sig.fn_token,
+            "the test function must be async",
+        ));
+    }
+
+    // The first argument is the bot, the rest stay for other test attributes to fill in
+    let inputs = std::mem::take(&mut function.sig.inputs);
+    let mut inputs = inputs.into_iter();
+    let Some(FnArg::Typed(bot)) = inputs.next() else {
+        return Err(syn::Error::new_spanned(
+            &function.sig,
+            "the first argument must be the MockBot, like `mut bot: MockBot<HandlerError, DistributionKey>`",
+        ));
+    };
+    function.sig.inputs = inputs.collect();
+
+    let bot_pat = &bot.pat;
+    let bot_ty = &bot.ty;
+    let block = &function.block;
+    let runtime_attr = runtime.then(|| quote!(#[::tokio::test]));
+    let attrs = &function.attrs;
+    let vis = &function.vis;
+    let sig = &function.sig;
+
+    Ok(quote! {
+        #runtime_attr
+        #(#attrs)*
+        #vis #sig {
+            let #bot_pat: #bot_ty = ::teremock::MockBot::new(#update, #tree).await;
+            #block
+        }
+    })
+}